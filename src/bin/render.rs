@@ -0,0 +1,53 @@
+//! Writes every generation of the Day 11 seat simulations and of the Day 17
+//! pocket dimensions as text frames, and the Day 12 routes as SVG pictures,
+//! from the inputs under `input/2020`:
+//!
+//! ```text
+//! cargo run --bin render -- <directory> [--colour] [--single-file]
+//! ```
+use std::{env, error::Error, fs};
+
+use advent2020::{
+    day11::{self, SimulationRules, Simulator},
+    day12, day17,
+    render::Renderer,
+};
+
+const USAGE: &str = "usage: render <directory> [--colour] [--single-file]";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut directory = None;
+    let (mut colour, mut single_file) = (false, false);
+    for argument in env::args().skip(1) {
+        match argument.as_str() {
+            "--colour" => colour = true,
+            "--single-file" => single_file = true,
+            _ if directory.is_none() => directory = Some(argument),
+            _ => return Err(USAGE.into()),
+        }
+    }
+    let mut renderer = Renderer::new(directory.ok_or(USAGE)?);
+    renderer.colour(colour).single_file(single_file);
+
+    let layout = day11::parse_input_day11(&fs::read_to_string("input/2020/day11.txt")?)?;
+    for (name, rules) in [
+        ("day11_part1", SimulationRules::part1()),
+        ("day11_part2", SimulationRules::part2()),
+    ] {
+        let mut simulator = Simulator::new(rules, &layout);
+        simulator.run_to_end();
+        renderer.write(name, &simulator.frames())?;
+    }
+
+    let commands = day12::parse_input_day12(&fs::read_to_string("input/2020/day12.txt")?)?;
+    renderer.write_file("day12_part1.svg", &day12::sail(&commands)?.svg())?;
+    renderer.write_file(
+        "day12_part2.svg",
+        &day12::sail_by_waypoint(&commands)?.svg(),
+    )?;
+
+    let slice = day17::parse_input_day17(&fs::read_to_string("input/2020/day17.txt")?)?;
+    renderer.write("day17_part1", &day17::frames::<3>(&slice, 6))?;
+    renderer.write("day17_part2", &day17::frames::<4>(&slice, 6))?;
+    Ok(())
+}
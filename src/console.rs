@@ -0,0 +1,553 @@
+use std::{collections::HashMap, convert::TryFrom, error::Error, fmt, str::FromStr};
+
+use crate::parse::{ParseError, Source};
+
+/// Everything an instruction can read and change.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Registers {
+    /// Index of the next instruction, may point outside of the program.
+    pub pc: i64,
+    pub accumulator: i64,
+}
+
+/// A register went past what an `i64` holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a register overflowed")
+    }
+}
+
+impl Error for Overflow {}
+
+impl Registers {
+    /// Moves `pc` by `offset`.
+    pub fn jump(&mut self, offset: i64) -> Result<(), Overflow> {
+        self.pc = self.pc.checked_add(offset).ok_or(Overflow)?;
+        Ok(())
+    }
+
+    pub fn add(&mut self, value: i64) -> Result<(), Overflow> {
+        self.accumulator = self.accumulator.checked_add(value).ok_or(Overflow)?;
+        Ok(())
+    }
+}
+
+/// The behaviour behind an opcode, responsible for moving `pc` as well.
+/// Registers left half changed by an overflow are thrown away.
+pub trait Operation {
+    fn mnemonic(&self) -> &'static str;
+    fn execute(&self, argument: i64, registers: &mut Registers) -> Result<(), Overflow>;
+
+    /// The opcode this one may have been corrupted from, and can be
+    /// swapped back to when repairing a program.
+    fn swapped_with(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+struct Nop;
+
+impl Operation for Nop {
+    fn mnemonic(&self) -> &'static str {
+        "nop"
+    }
+
+    fn execute(&self, _: i64, registers: &mut Registers) -> Result<(), Overflow> {
+        registers.jump(1)
+    }
+
+    fn swapped_with(&self) -> Option<&'static str> {
+        Some("jmp")
+    }
+}
+
+struct Acc;
+
+impl Operation for Acc {
+    fn mnemonic(&self) -> &'static str {
+        "acc"
+    }
+
+    fn execute(&self, argument: i64, registers: &mut Registers) -> Result<(), Overflow> {
+        registers.add(argument)?;
+        registers.jump(1)
+    }
+}
+
+struct Jmp;
+
+impl Operation for Jmp {
+    fn mnemonic(&self) -> &'static str {
+        "jmp"
+    }
+
+    fn execute(&self, argument: i64, registers: &mut Registers) -> Result<(), Overflow> {
+        registers.jump(argument)
+    }
+
+    fn swapped_with(&self) -> Option<&'static str> {
+        Some("nop")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Instruction {
+    pub opcode: &'static str,
+    pub argument: i64,
+}
+
+impl Instruction {
+    pub fn new(opcode: &'static str, argument: i64) -> Self {
+        Self { opcode, argument }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:+}", self.opcode, self.argument)
+    }
+}
+
+/// Reads an instruction of the handheld console. `Display` writes the
+/// canonical `opcode +argument` form, which reads back as the same
+/// instruction, while other spellings like `acc 1` are accepted too but
+/// written back canonically. Errors point into `text` alone, with no day,
+/// `InstructionSet::instruction` takes the caller's input to point into.
+impl FromStr for Instruction {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        InstructionSet::handheld().instruction(&Source::new(0, text), text)
+    }
+}
+
+fn operands<'a>(source: &Source, line: &'a str) -> Result<(&'a str, &'a str), ParseError> {
+    let mut parts = line.split_whitespace();
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(opcode), Some(argument), None) => Ok((opcode, argument)),
+        _ => Err(source.error(line, "an instruction and its argument")),
+    }
+}
+
+/// The opcodes a machine understands, keyed by mnemonic.
+#[derive(Default)]
+pub struct InstructionSet {
+    operations: HashMap<&'static str, Box<dyn Operation>>,
+}
+
+impl InstructionSet {
+    /// `nop`, `acc` and `jmp`, as found on the handheld game console.
+    pub fn handheld() -> Self {
+        let mut set = Self::default();
+        set.register(Nop).register(Acc).register(Jmp);
+        set
+    }
+
+    /// Adds an opcode, replacing any other one with the same mnemonic.
+    pub fn register(&mut self, operation: impl Operation + 'static) -> &mut Self {
+        self.operations
+            .insert(operation.mnemonic(), Box::new(operation));
+        self
+    }
+
+    pub fn get(&self, opcode: &str) -> Option<&dyn Operation> {
+        self.operations
+            .get(opcode)
+            .map(|operation| operation.as_ref())
+    }
+
+    fn opcode(&self, source: &Source, opcode: &str) -> Result<&'static str, ParseError> {
+        self.get(opcode)
+            .map(|operation| operation.mnemonic())
+            .ok_or_else(|| source.error(opcode, "a known opcode"))
+    }
+
+    /// Parses a single `opcode +argument` instruction, its two parts split
+    /// by any amount of whitespace.
+    pub fn instruction(&self, source: &Source, line: &str) -> Result<Instruction, ParseError> {
+        let (opcode, argument) = operands(source, line)?;
+        Ok(Instruction::new(
+            self.opcode(source, opcode)?,
+            source.parse(argument, "a signed argument")?,
+        ))
+    }
+
+    /// Parses one `opcode +argument` instruction per line.
+    pub fn parse(&self, source: &Source, text: &str) -> Result<Vec<Instruction>, ParseError> {
+        text.lines()
+            .map(|line| self.instruction(source, line))
+            .collect()
+    }
+
+    /// Like `parse`, but instructions can start with a `label:` and take
+    /// `@label` as argument, which becomes the offset to that label.
+    /// Blank lines are skipped and a label on its own names the next
+    /// instruction, or the end of the program.
+    pub fn assemble(&self, source: &Source, text: &str) -> Result<Vec<Instruction>, ParseError> {
+        let mut labels = HashMap::new();
+        let mut lines = Vec::new();
+        for line in text.lines() {
+            let mut body = line.trim();
+            if let Some((label, rest)) = body.split_once(':') {
+                let label = label.trim();
+                if label.is_empty() || !label.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    return Err(source.error(label, "a label made of letters, digits and `_`"));
+                }
+                if labels.insert(label, lines.len()).is_some() {
+                    return Err(source.error(label, "a label that isn't defined yet"));
+                }
+                body = rest.trim();
+            }
+            if !body.is_empty() {
+                lines.push(body);
+            }
+        }
+
+        lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let (opcode, argument) = operands(source, line)?;
+                match argument.strip_prefix('@') {
+                    Some(label) => {
+                        let target = labels
+                            .get(label)
+                            .ok_or_else(|| source.error(argument, "a defined label"))?;
+                        Ok(Instruction::new(
+                            self.opcode(source, opcode)?,
+                            *target as i64 - index as i64,
+                        ))
+                    }
+                    None => self.instruction(source, line),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Why a machine stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Halt {
+    /// `pc` landed right after the last instruction.
+    Terminated,
+    /// The instruction at `pc` was about to run a second time.
+    InfiniteLoop { pc: usize },
+    /// `pc` landed anywhere else outside of the program.
+    OutOfBounds { pc: i64 },
+    /// The instruction at `pc` isn't part of the machine's instruction set.
+    UnknownOpcode { pc: usize },
+    /// Running the instruction at `pc` would overflow a register, so it
+    /// was left unrun.
+    Overflow { pc: usize },
+    /// The instruction at `pc` matched a breakpoint and hasn't run yet,
+    /// running again resumes from it.
+    Breakpoint { pc: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    Pc(usize),
+    Opcode(&'static str),
+}
+
+/// One executed instruction and what it did to the accumulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub pc: usize,
+    pub instruction: Instruction,
+    pub accumulator_before: i64,
+    pub accumulator_after: i64,
+}
+
+/// Every instruction a machine executed, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub entries: Vec<TraceEntry>,
+}
+
+fn json_string(text: &str) -> String {
+    let mut result = String::with_capacity(text.len() + 2);
+    result.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+impl Trace {
+    /// An array with one object per entry, instructions written as text.
+    pub fn to_json(&self) -> String {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{{\"pc\":{},\"instruction\":{},\"acc_before\":{},\"acc_after\":{}}}",
+                    entry.pc,
+                    json_string(&entry.instruction.to_string()),
+                    entry.accumulator_before,
+                    entry.accumulator_after
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("[{}]", entries.join(","))
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in self.entries.iter() {
+            writeln!(
+                f,
+                "{:>5}: {}  acc {} -> {}",
+                entry.pc, entry.instruction, entry.accumulator_before, entry.accumulator_after
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Machine<'a> {
+    instruction_set: &'a InstructionSet,
+    program: &'a [Instruction],
+    registers: Registers,
+    visited: Vec<bool>,
+    breakpoints: Vec<Breakpoint>,
+    // Set when `run` stopped on a breakpoint, so the next `run` steps over it
+    paused: bool,
+    trace: Option<Trace>,
+}
+
+impl<'a> Machine<'a> {
+    pub fn new(instruction_set: &'a InstructionSet, program: &'a [Instruction]) -> Self {
+        Self {
+            instruction_set,
+            program,
+            registers: Registers::default(),
+            visited: vec![false; program.len()],
+            breakpoints: Vec::new(),
+            paused: false,
+            trace: None,
+        }
+    }
+
+    /// Starts recording every instruction executed from now on.
+    pub fn record_trace(&mut self) {
+        self.trace.get_or_insert_with(Trace::default);
+    }
+
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+    }
+
+    fn at_breakpoint(&self) -> Option<usize> {
+        let pc = usize::try_from(self.registers.pc).ok()?;
+        let instruction = self.program.get(pc)?;
+        self.breakpoints
+            .iter()
+            .any(|&breakpoint| match breakpoint {
+                Breakpoint::Pc(at) => at == pc,
+                Breakpoint::Opcode(opcode) => opcode == instruction.opcode,
+            })
+            .then_some(pc)
+    }
+
+    pub fn registers(&self) -> Registers {
+        self.registers
+    }
+
+    /// Why the machine can't run the next instruction, if it can't.
+    pub fn halted(&self) -> Option<Halt> {
+        let pc = self.registers.pc;
+        match usize::try_from(pc) {
+            Ok(index) if index == self.program.len() => Some(Halt::Terminated),
+            Ok(index) if index < self.program.len() => {
+                if self.visited[index] {
+                    Some(Halt::InfiniteLoop { pc: index })
+                } else if self
+                    .instruction_set
+                    .get(self.program[index].opcode)
+                    .is_none()
+                {
+                    Some(Halt::UnknownOpcode { pc: index })
+                } else {
+                    None
+                }
+            }
+            _ => Some(Halt::OutOfBounds { pc }),
+        }
+    }
+
+    /// Runs a single instruction, or returns why it couldn't.
+    pub fn step(&mut self) -> Result<(), Halt> {
+        if let Some(halt) = self.halted() {
+            return Err(halt);
+        }
+        let index = self.registers.pc as usize;
+        let instruction = self.program[index];
+        let accumulator_before = self.registers.accumulator;
+        let mut registers = self.registers;
+        self.instruction_set
+            .get(instruction.opcode)
+            .unwrap()
+            .execute(instruction.argument, &mut registers)
+            .map_err(|_| Halt::Overflow { pc: index })?;
+        self.registers = registers;
+        self.visited[index] = true;
+        self.paused = false;
+
+        if let Some(trace) = self.trace.as_mut() {
+            trace.entries.push(TraceEntry {
+                pc: index,
+                instruction,
+                accumulator_before,
+                accumulator_after: self.registers.accumulator,
+            });
+        }
+        Ok(())
+    }
+
+    /// Steps until the machine halts or reaches a breakpoint.
+    pub fn run(&mut self) -> Halt {
+        loop {
+            if let (false, Some(pc)) = (self.paused, self.at_breakpoint()) {
+                self.paused = true;
+                return Halt::Breakpoint { pc };
+            }
+            if let Err(halt) = self.step() {
+                return halt;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(set: &InstructionSet, text: &str) -> (Halt, i64) {
+        let program = set.parse(&Source::new(0, text), text).unwrap();
+        let mut machine = Machine::new(set, &program);
+        let halt = machine.run();
+        (halt, machine.registers().accumulator)
+    }
+
+    #[test]
+    fn test_halt_reasons() {
+        let set = InstructionSet::handheld();
+        assert_eq!(run(&set, "acc -3\nnop +0"), (Halt::Terminated, -3));
+        assert_eq!(
+            run(&set, "acc +1\njmp -1"),
+            (Halt::InfiniteLoop { pc: 0 }, 1)
+        );
+        assert_eq!(
+            run(&set, "acc +2\njmp -4"),
+            (Halt::OutOfBounds { pc: -3 }, 2)
+        );
+
+        assert_eq!(
+            run(&set, "acc +9223372036854775807\nacc +1"),
+            (Halt::Overflow { pc: 1 }, i64::MAX)
+        );
+        assert_eq!(
+            run(&set, "nop +0\njmp +9223372036854775807"),
+            (Halt::Overflow { pc: 1 }, 0)
+        );
+
+        let program = [Instruction::new("mul", 2)];
+        assert_eq!(
+            Machine::new(&set, &program).run(),
+            Halt::UnknownOpcode { pc: 0 }
+        );
+    }
+
+    #[test]
+    fn test_custom_opcode() {
+        struct Mul;
+
+        impl Operation for Mul {
+            fn mnemonic(&self) -> &'static str {
+                "mul"
+            }
+
+            fn execute(&self, argument: i64, registers: &mut Registers) -> Result<(), Overflow> {
+                registers.accumulator = registers
+                    .accumulator
+                    .checked_mul(argument)
+                    .ok_or(Overflow)?;
+                registers.jump(1)
+            }
+        }
+
+        let mut set = InstructionSet::handheld();
+        set.register(Mul);
+        assert_eq!(run(&set, "acc +3\nmul -4"), (Halt::Terminated, -12));
+    }
+
+    #[test]
+    fn test_trace_and_breakpoints() {
+        let set = InstructionSet::handheld();
+        let text = "nop +0\nacc +1\njmp -1";
+        let program = set.parse(&Source::new(0, text), text).unwrap();
+        let mut machine = Machine::new(&set, &program);
+        machine.record_trace();
+        machine.add_breakpoint(Breakpoint::Opcode("jmp"));
+        assert_eq!(machine.run(), Halt::Breakpoint { pc: 2 });
+        assert_eq!(machine.run(), Halt::InfiniteLoop { pc: 1 });
+
+        let trace = machine.trace().unwrap();
+        assert_eq!(
+            trace.to_string(),
+            "    0: nop +0  acc 0 -> 0\n    1: acc +1  acc 0 -> 1\n    2: jmp -1  acc 1 -> 1\n"
+        );
+        assert_eq!(
+            trace.to_json(),
+            "[{\"pc\":0,\"instruction\":\"nop +0\",\"acc_before\":0,\"acc_after\":0},\
+             {\"pc\":1,\"instruction\":\"acc +1\",\"acc_before\":0,\"acc_after\":1},\
+             {\"pc\":2,\"instruction\":\"jmp -1\",\"acc_before\":1,\"acc_after\":1}]"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_and_assemble() {
+        for instruction in [
+            Instruction::new("nop", 0),
+            Instruction::new("acc", 1),
+            Instruction::new("jmp", -99),
+        ] {
+            assert_eq!(instruction.to_string().parse(), Ok(instruction));
+        }
+        let instruction = "acc  1".parse::<Instruction>().unwrap();
+        assert_eq!(instruction.to_string(), "acc +1");
+        assert!("mul +2".parse::<Instruction>().is_err());
+        assert!("acc +1 +2".parse::<Instruction>().is_err());
+
+        let set = InstructionSet::handheld();
+        let text = "acc +1\nloop: jmp   @end\n\njmp\t@loop\nend:";
+        assert_eq!(
+            set.assemble(&Source::new(0, text), text),
+            Ok(vec![
+                Instruction::new("acc", 1),
+                Instruction::new("jmp", 2),
+                Instruction::new("jmp", -1),
+            ])
+        );
+
+        let text = "loop: jmp @start";
+        let error = set.assemble(&Source::new(0, text), text).unwrap_err();
+        assert_eq!((error.column, error.expected), (11, "a defined label"));
+    }
+}
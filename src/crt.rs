@@ -0,0 +1,100 @@
+use std::{error::Error, fmt};
+
+use crate::modular::extended_gcd;
+
+/// A congruence `x ≡ remainder (mod modulus)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Congruence {
+    pub remainder: i128,
+    pub modulus: i128,
+}
+
+impl Congruence {
+    pub fn new(remainder: i128, modulus: i128) -> Self {
+        Self {
+            remainder: remainder.rem_euclid(modulus),
+            modulus,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CrtError {
+    /// Two congruences disagree on a shared factor of their moduli.
+    NoSolution,
+    /// The combined modulus doesn't fit in an `i128`.
+    Overflow,
+}
+
+impl fmt::Display for CrtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrtError::NoSolution => write!(f, "the congruences have no common solution"),
+            CrtError::Overflow => write!(f, "the combined modulus overflows an i128"),
+        }
+    }
+}
+
+impl Error for CrtError {}
+
+fn combine(first: Congruence, second: Congruence) -> Result<Congruence, CrtError> {
+    let (g, p, _) = extended_gcd(first.modulus, second.modulus);
+    let diff = second.remainder - first.remainder;
+    if diff % g != 0 {
+        return Err(CrtError::NoSolution);
+    }
+
+    // first.remainder + first.modulus * k ≡ second.remainder (mod second.modulus)
+    // where k ≡ (diff / g) * p (mod second.modulus / g)
+    let reduced = second.modulus / g;
+    let k = (diff / g)
+        .rem_euclid(reduced)
+        .checked_mul(p.rem_euclid(reduced))
+        .ok_or(CrtError::Overflow)?
+        .rem_euclid(reduced);
+    let modulus = (first.modulus / g)
+        .checked_mul(second.modulus)
+        .ok_or(CrtError::Overflow)?;
+    let remainder = first
+        .modulus
+        .checked_mul(k)
+        .and_then(|offset| offset.checked_add(first.remainder))
+        .ok_or(CrtError::Overflow)?;
+
+    Ok(Congruence::new(remainder, modulus))
+}
+
+/// Solves a system of congruences, moduli don't need to be pairwise coprime.
+/// The result holds the smallest non-negative solution, with the lcm of all
+/// moduli as its modulus.
+pub fn solve(congruences: &[Congruence]) -> Result<Congruence, CrtError> {
+    congruences
+        .iter()
+        .try_fold(Congruence::new(0, 1), |acc, &congruence| {
+            combine(acc, congruence)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coprime() {
+        let congruences = [
+            Congruence::new(2, 3),
+            Congruence::new(3, 5),
+            Congruence::new(2, 7),
+        ];
+        assert_eq!(solve(&congruences), Ok(Congruence::new(23, 105)));
+    }
+
+    #[test]
+    fn test_not_coprime() {
+        let congruences = [Congruence::new(2, 4), Congruence::new(4, 6)];
+        assert_eq!(solve(&congruences), Ok(Congruence::new(10, 12)));
+
+        let congruences = [Congruence::new(1, 4), Congruence::new(2, 6)];
+        assert_eq!(solve(&congruences), Err(CrtError::NoSolution));
+    }
+}
@@ -1,10 +1,15 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::cmp::Ordering;
-use std::num::ParseIntError;
+
+use crate::parse::{ParseError, Source};
 
 #[aoc_generator(day1)]
-fn parse_input_day1(input: &str) -> Result<Vec<i32>, ParseIntError> {
-    input.lines().map(|l| l.parse()).collect()
+fn parse_input_day1(input: &str) -> Result<Vec<i32>, ParseError> {
+    let source = Source::new(1, input);
+    input
+        .lines()
+        .map(|l| source.parse(l, "an expense"))
+        .collect()
 }
 
 #[aoc(day1, part1)]
@@ -1,19 +1,37 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::parse::{ParseError, Source};
+
 #[aoc_generator(day10)]
-fn parse_input_day10(input: &str) -> Vec<usize> {
+fn parse_input_day10(input: &str) -> Result<Vec<usize>, ParseError> {
+    let source = Source::new(10, input);
     let mut rates = input
         .lines()
-        .map(|rate| rate.parse().unwrap())
-        .collect::<Vec<usize>>();
+        .map(|rate| Ok((source.parse::<usize>(rate, "a joltage rating")?, rate)))
+        .collect::<Result<Vec<_>, ParseError>>()?;
     rates.sort_unstable();
+    if rates.is_empty() {
+        return Err(source.end("at least one joltage rating"));
+    }
 
-    rates
+    // Every adapter has to plug into the one before it, the outlet at 0 first
+    let mut previous = 0;
+    for &(rate, text) in rates.iter() {
+        if rate == previous {
+            return Err(source.error(text, "a rating used only once, and not 0"));
+        }
+        if rate - previous > 3 {
+            return Err(source.error(text, "a rating at most 3 above the next lower one"));
+        }
+        previous = rate;
+    }
+
+    Ok(rates.into_iter().map(|(rate, _)| rate).collect())
 }
 
 #[aoc(day10, part1)]
 fn day10_part1(rates: &[usize]) -> Option<usize> {
-    let mut record = vec![0, 0, 1]; // ones, twos, threes
+    let mut record = [0, 0, 1]; // ones, twos, threes
     rates.iter().fold(0, |acc, &node| {
         record[node - acc - 1] += 1;
         node
@@ -57,19 +75,25 @@ mod tests {
     #[test]
     fn test_part1() {
         let input = "16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4";
-        assert_eq!(day10_part1(&parse_input_day10(input)), Some(35));
+        assert_eq!(day10_part1(&parse_input_day10(input).unwrap()), Some(35));
 
         let input = "28\n33\n18\n42\n31\n14\n46\n20\n48\n47\n24\n23\n49\n45\n19\n38\n39\n11\n1\n32\n25\n35\n8\n17\n7\n9\n4\n2\n34\n10\n3";
-        assert_eq!(day10_part1(&parse_input_day10(input)), Some(220));
+        assert_eq!(day10_part1(&parse_input_day10(input).unwrap()), Some(220));
     }
 
     #[test]
     fn test_part2() {
         let input = "16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4";
-        assert_eq!(day10_part2(&parse_input_day10(input)), Some(8));
+        assert_eq!(day10_part2(&parse_input_day10(input).unwrap()), Some(8));
 
         let input = "28\n33\n18\n42\n31\n14\n46\n20\n48\n47\n24\n23\n49\n45\n19\n38\n39\n11\n1\n32\n25\n35\n8\n17\n7\n9\n4\n2\n34\n10\n3";
-        assert_eq!(day10_part2(&parse_input_day10(input)), Some(19208));
+        assert_eq!(day10_part2(&parse_input_day10(input).unwrap()), Some(19208));
+
+        for input in ["", "1\n2\n2", "0\n1", "1\n5"] {
+            assert!(parse_input_day10(input).is_err());
+        }
+        let error = parse_input_day10("3\n1\n7").unwrap_err();
+        assert_eq!((error.line, error.text.as_str()), (3, "7"));
     }
 }
 
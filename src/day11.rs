@@ -1,219 +1,152 @@
-use aoc_runner_derive::aoc;
-
-static DIRECTIONS: &[(isize, isize)] = &[
-    (0, 1),
-    (0, -1),
-    (1, 0),
-    (-1, 0),
-    (1, -1),
-    (1, 1),
-    (-1, 1),
-    (-1, -1),
-];
-
-struct Point {
-    x: isize,
-    y: isize,
-}
-
-impl Point {
-    fn new(x: isize, y: isize) -> Self {
-        Point { x, y }
-    }
-    fn step_t(&mut self, dxdy: &(isize, isize)) {
-        self.x += dxdy.0;
-        self.y += dxdy.1;
-    }
-    fn is_valid(&self, max_x: isize, max_y: isize) -> bool {
-        !(self.x < 0 || self.x >= max_x || self.y < 0 || self.y >= max_y)
-    }
-}
-
-#[derive(Debug, Default)]
-struct AdjacentCount {
-    floor: usize,
-    empty: usize,
-    occupied: usize,
-}
-
-enum AdjacentStrategy {
-    ClosestEight,
-    FirstSeenInEachDirection,
-}
+use aoc_runner_derive::{aoc, aoc_generator};
 
-impl AdjacentStrategy {
-    fn get_adjacent_seats(&self, board: &[Vec<char>], row: usize, column: usize) -> Vec<Point> {
-        match self {
-            Self::ClosestEight => self.get_points_1(board, row, column),
-            Self::FirstSeenInEachDirection => self.get_points_2(board, row, column),
-        }
-    }
+use crate::{
+    grid::{Grid, Position},
+    life::{Adjacency, Life, Neighborhood, Outcome, Plane, Rule, Topology},
+    parse::{ParseError, Source},
+    render::Frame,
+};
 
-    fn get_points_1(&self, board: &[Vec<char>], row: usize, column: usize) -> Vec<Point> {
-        let mut result = Vec::new();
-        let max_x = board.len() as isize;
-        let max_y = board.first().unwrap().len() as isize;
-
-        for change in DIRECTIONS.iter() {
-            let mut point = Point::new(row as isize, column as isize);
-            point.step_t(change);
-            if !point.is_valid(max_x, max_y) {
-                continue;
-            }
-            result.push(point);
-        }
-        result
-    }
-
-    fn get_points_2(&self, board: &[Vec<char>], row: usize, column: usize) -> Vec<Point> {
-        let mut result = Vec::new();
-        let max_x = board.len() as isize;
-        let max_y = board.first().unwrap().len() as isize;
-
-        for change in DIRECTIONS.iter() {
-            let mut point = Point::new(row as isize, column as isize);
-            loop {
-                point.step_t(change);
-                if !point.is_valid(max_x, max_y) {
-                    break;
-                }
-                match board[point.x as usize][point.y as usize] {
-                    '.' => continue,
-                    _ => {
-                        result.push(point);
-                        break;
-                    }
-                }
-            }
-        }
-        result
-    }
-}
+/// Far past where real layouts settle, a simulation that hasn't repeated by
+/// then gives up.
+const MAX_GENERATIONS: usize = 10_000;
 
-struct SimulationRules {
+pub struct SimulationRules {
     max_occupied_to_sit: usize,
     min_occupied_to_leave: usize,
-    adjacent_strategy: AdjacentStrategy,
+    neighborhood: Neighborhood,
 }
 
 impl SimulationRules {
-    fn new(
+    pub fn new(
         max_occupied_to_sit: usize,
         min_occupied_to_leave: usize,
-        adjacent_strategy: AdjacentStrategy,
+        neighborhood: Neighborhood,
     ) -> Self {
         SimulationRules {
             max_occupied_to_sit,
             min_occupied_to_leave,
-            adjacent_strategy,
+            neighborhood,
         }
     }
-}
 
-struct SimulationStep {
-    board: Vec<Vec<char>>,
-    changes: usize,
-}
+    /// Seats taken next to nobody, left next to 4 others.
+    pub fn part1() -> Self {
+        Self::new(0, 4, Neighborhood::Adjacent)
+    }
+
+    /// Seats taken seeing nobody, left seeing 5 others.
+    pub fn part2() -> Self {
+        Self::new(0, 5, Neighborhood::LineOfSight)
+    }
 
-impl SimulationStep {
-    fn new(board: Vec<Vec<char>>) -> Self {
-        SimulationStep { board, changes: 0 }
+    fn rule(&self) -> Rule {
+        let survive = (0..self.min_occupied_to_leave).collect::<Vec<_>>();
+        Rule::new(&[self.max_occupied_to_sit], &survive)
     }
 }
 
-struct Simulator {
-    rules: SimulationRules,
-    step: SimulationStep,
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationStep {
+    /// One byte per seat, 1 when taken.
+    pub occupied: Vec<u8>,
+    /// How many seats changed since the previous step.
+    pub changes: usize,
+}
+
+/// The seats as a `Life` on their adjacency, floor left out: seat `i` is
+/// at `seats[i]`.
+pub struct Simulator {
+    life: Life<Adjacency>,
+    seats: Vec<Position>,
+    layout: Grid<char>,
+    history: Vec<SimulationStep>,
 }
 
 impl Simulator {
-    fn new(rules: SimulationRules, initial_step: SimulationStep) -> Self {
+    pub fn new(rules: SimulationRules, board: &Grid<char>) -> Self {
+        let plane = Plane::new(board.map(|&cell| cell != '.'), rules.neighborhood);
+        let seats = plane.cells().unwrap_or_default();
+        let adjacency = Adjacency::new(&plane, &seats);
+        let occupied = (0..seats.len()).filter(|&seat| board[seats[seat]] == '#');
+        let life = Life::new(adjacency, rules.rule(), occupied);
+        let history = vec![SimulationStep {
+            occupied: life.state().cells().to_vec(),
+            changes: 0,
+        }];
         Self {
-            rules,
-            step: initial_step,
+            life,
+            seats,
+            layout: board.clone(),
+            history,
         }
     }
 
-    fn run_to_end(&mut self) {
-        loop {
-            self.step = self.next_step();
-            if self.step.changes == 0 {
-                break;
-            }
+    fn board(&self, step: &SimulationStep) -> Grid<char> {
+        let mut board = self.layout.clone();
+        for (&position, &occupied) in self.seats.iter().zip(step.occupied.iter()) {
+            board[position] = if occupied == 1 { '#' } else { 'L' };
         }
+        board
     }
 
-    fn count_adjacent_seats(&self, row: usize, column: usize) -> AdjacentCount {
-        let mut counts = AdjacentCount::default();
-        let adjacent_seats =
-            self.rules
-                .adjacent_strategy
-                .get_adjacent_seats(&self.step.board, row, column);
-        for seat in adjacent_seats.iter() {
-            match self.step.board[seat.x as usize][seat.y as usize] {
-                'L' => counts.empty += 1,
-                '#' => counts.occupied += 1,
-                '.' => counts.floor += 1,
-                _ => unreachable!(),
-            }
-        }
-        counts
+    /// Steps until the seats are taken the same way a second time, none
+    /// when that takes more than `MAX_GENERATIONS`.
+    pub fn run_to_end(&mut self) -> Option<Outcome> {
+        let history = &mut self.history;
+        self.life.run_to_end(MAX_GENERATIONS, |life, changes| {
+            history.push(SimulationStep {
+                occupied: life.state().cells().to_vec(),
+                changes,
+            })
+        })
     }
 
-    fn next_step(&self) -> SimulationStep {
-        let mut new_step = SimulationStep::new(self.step.board.clone());
-        let rows = self.step.board.len();
-        let columns = self.step.board.first().unwrap().len();
-        for row in 0..rows {
-            for column in 0..columns {
-                let seats_count = self.count_adjacent_seats(row, column);
-                if self.step.board[row][column] == 'L'
-                    && seats_count.occupied == self.rules.max_occupied_to_sit
-                {
-                    new_step.board[row][column] = '#';
-                    new_step.changes += 1;
-                } else if self.step.board[row][column] == '#'
-                    && seats_count.occupied >= self.rules.min_occupied_to_leave
-                {
-                    new_step.board[row][column] = 'L';
-                    new_step.changes += 1;
-                }
-            }
-        }
-        new_step
+    /// Every generation so far as a frame, for `Renderer::write`.
+    pub fn frames(&self) -> Vec<Frame> {
+        self.history
+            .iter()
+            .enumerate()
+            .map(|(generation, step)| {
+                let title = format!("Generation {}, {} changes", generation, step.changes);
+                Frame::new(title).slice("", self.board(step))
+            })
+            .collect()
     }
 
     fn count_occupied(&self) -> usize {
-        self.step
-            .board
-            .iter()
-            .map(|row| row.iter().filter(|&&seat| seat == '#').count())
-            .sum()
+        self.life.population()
     }
 }
 
-fn parse_input_day11(input: &str) -> Vec<Vec<char>> {
-    input.lines().map(|line| line.chars().collect()).collect()
+fn simulate(rules: SimulationRules, board: &Grid<char>) -> (Option<Outcome>, usize) {
+    let mut simulator = Simulator::new(rules, board);
+    let outcome = simulator.run_to_end();
+    (outcome, simulator.count_occupied())
 }
 
-#[aoc(day11, part1)]
-fn day11_part1(input: &str) -> Option<usize> {
-    let board = parse_input_day11(input);
-    let rules = SimulationRules::new(0, 4, AdjacentStrategy::ClosestEight);
-    let mut simulator = Simulator::new(rules, SimulationStep::new(board));
-    simulator.run_to_end();
+#[aoc_generator(day11)]
+pub fn parse_input_day11(input: &str) -> Result<Grid<char>, ParseError> {
+    let source = Source::new(11, input);
+    Grid::parse(&source, input, "`L`, `#` or `.`", |c| {
+        matches!(c, 'L' | '#' | '.').then_some(c)
+    })
+}
 
-    Some(simulator.count_occupied())
+#[aoc(day11, part1)]
+fn day11_part1(board: &Grid<char>) -> Option<usize> {
+    match simulate(SimulationRules::part1(), board) {
+        (Some(Outcome::Stable { .. }), occupied) => Some(occupied),
+        _ => None,
+    }
 }
 
 #[aoc(day11, part2)]
-fn day11_part2(input: &str) -> Option<usize> {
-    let board = parse_input_day11(input);
-    let rules = SimulationRules::new(0, 5, AdjacentStrategy::FirstSeenInEachDirection);
-    let mut simulator = Simulator::new(rules, SimulationStep::new(board));
-    simulator.run_to_end();
-
-    Some(simulator.count_occupied())
+fn day11_part2(board: &Grid<char>) -> Option<usize> {
+    match simulate(SimulationRules::part2(), board) {
+        (Some(Outcome::Stable { .. }), occupied) => Some(occupied),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -223,12 +156,49 @@ mod tests {
     #[test]
     fn test_part1() {
         let input = "L.LL.LL.LL\nLLLLLLL.LL\nL.L.L..L..\nLLLL.LL.LL\nL.LL.LL.LL\nL.LLLLL.LL\n..L.L.....\nLLLLLLLLLL\nL.LLLLLL.L\nL.LLLLL.LL";
-        assert_eq!(day11_part1(input), Some(37));
+        assert_eq!(day11_part1(&parse_input_day11(input).unwrap()), Some(37));
+
+        let mut simulator =
+            Simulator::new(SimulationRules::part1(), &parse_input_day11(input).unwrap());
+        assert_eq!(
+            simulator.run_to_end(),
+            Some(Outcome::Stable { generation: 5 })
+        );
+        assert_eq!(simulator.history.len(), 7);
+        assert_eq!(simulator.history[6].changes, 0);
+        let frames = simulator.frames();
+        assert_eq!(frames[1].title, "Generation 1, 71 changes");
+        assert_eq!(
+            frames[1].slices[0].1.row(0),
+            "#.##.##.##".chars().collect::<Vec<_>>()
+        );
+
+        // The lone seat stays taken, the pair keeps sitting down and leaving
+        let rules = SimulationRules::new(0, 1, Neighborhood::Adjacent);
+        let board = parse_input_day11("L.LL").unwrap();
+        assert_eq!(
+            simulate(rules, &board),
+            (
+                Some(Outcome::Cycle {
+                    start: 1,
+                    period: 2
+                }),
+                3
+            )
+        );
     }
 
     #[test]
     fn test_part2() {
         let input = "L.LL.LL.LL\nLLLLLLL.LL\nL.L.L..L..\nLLLL.LL.LL\nL.LL.LL.LL\nL.LLLLL.LL\n..L.L.....\nLLLLLLLLLL\nL.LLLLLL.L\nL.LLLLL.LL";
-        assert_eq!(day11_part2(input), Some(26));
+        assert_eq!(day11_part2(&parse_input_day11(input).unwrap()), Some(26));
+
+        let layout = parse_input_day11(".............\n.L.L.#.#.#.#.\n.............").unwrap();
+        let simulator = Simulator::new(SimulationRules::part2(), &layout);
+        assert_eq!(simulator.seats[0], (1, 1));
+        assert_eq!(simulator.life.topology().row(0), &[1]);
+        assert_eq!(simulator.life.topology().row(1), &[2, 0]);
+        let simulator = Simulator::new(SimulationRules::part1(), &layout);
+        assert_eq!(simulator.life.topology().row(0), &[] as &[usize]);
     }
 }
@@ -1,40 +1,71 @@
-use aoc_runner_derive::aoc;
+use std::{error::Error, fmt};
 
-#[derive(Debug, Copy, Clone)]
-struct Point {
-    x: f32,
-    y: f32,
+use aoc_runner_derive::{aoc, aoc_generator};
+
+use crate::parse::{ParseError, Source};
+
+/// Counter-clockwise rotations by 0, 90, 180 and 270 degrees.
+const QUARTER_TURNS: [[[i64; 2]; 2]; 4] = [
+    [[1, 0], [0, 1]],
+    [[0, -1], [1, 0]],
+    [[-1, 0], [0, -1]],
+    [[0, 1], [-1, 0]],
+];
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
 }
 
 impl Point {
-    fn new(x: f32, y: f32) -> Self {
+    fn new(x: i64, y: i64) -> Self {
         Self { x, y }
     }
 
     fn origin() -> Self {
-        Point::new(0.0, 0.0)
+        Point::new(0, 0)
+    }
+
+    /// None when the point is too far out for its Manhattan distance to
+    /// fit in an `i64`, which no point of a route ever is.
+    fn checked(x: i64, y: i64) -> Option<Self> {
+        x.checked_abs()?.checked_add(y.checked_abs()?)?;
+        Some(Point::new(x, y))
+    }
+
+    fn offset(self, dx: i64, dy: i64) -> Option<Self> {
+        Point::checked(self.x.checked_add(dx)?, self.y.checked_add(dy)?)
     }
 
-    fn step(&mut self, dx: f32, dy: f32) {
-        self.x += dx;
-        self.y += dy;
+    fn scaled(self, factor: i64) -> Option<Self> {
+        Point::checked(self.x.checked_mul(factor)?, self.y.checked_mul(factor)?)
     }
 
-    fn manhattan(&self) -> f32 {
+    fn manhattan(&self) -> i64 {
         self.x.abs() + self.y.abs()
     }
+
+    /// Turns the point around the origin, counter-clockwise for positive
+    /// `quarter_turns`. Only swaps and negates coordinates, which a point
+    /// that passed `checked` survives.
+    fn rotate(&mut self, quarter_turns: i64) {
+        let [[a, b], [c, d]] = QUARTER_TURNS[quarter_turns.rem_euclid(4) as usize];
+        *self = Point::new(a * self.x + b * self.y, c * self.x + d * self.y);
+    }
 }
 
 #[derive(Debug)]
 struct Vector {
-    angle: f32,
+    /// One unit along where the vector points.
+    heading: Point,
     position: Point,
 }
 
 impl Vector {
     fn new(position: Point) -> Self {
         Self {
-            angle: 0.0,
+            heading: Point::new(1, 0),
             position,
         }
     }
@@ -43,117 +74,281 @@ impl Vector {
         Self::new(Point::origin())
     }
 
-    fn rotate(&mut self, angle: f32) {
-        self.angle += angle;
+    fn rotate(&mut self, quarter_turns: i64) {
+        self.heading.rotate(quarter_turns);
     }
 
-    fn forward(&mut self, distance: f32) {
-        self.position.step(
-            distance * self.angle.to_radians().cos(),
-            distance * self.angle.to_radians().sin(),
-        )
+    fn shift(&mut self, dx: i64, dy: i64) -> Option<()> {
+        self.position = self.position.offset(dx, dy)?;
+        Some(())
     }
 
-    fn north(&mut self, distance: f32) {
-        self.position.step(0.0, distance)
+    fn forward(&mut self, distance: i64) -> Option<()> {
+        let step = self.heading.scaled(distance)?;
+        self.shift(step.x, step.y)
     }
 
-    fn east(&mut self, distance: f32) {
-        self.position.step(distance, 0.0)
+    fn north(&mut self, distance: i64) -> Option<()> {
+        self.shift(0, distance)
     }
 
-    fn south(&mut self, distance: f32) {
-        self.position.step(0.0, -distance)
+    fn east(&mut self, distance: i64) -> Option<()> {
+        self.shift(distance, 0)
     }
 
-    fn west(&mut self, distance: f32) {
-        self.position.step(-distance, 0.0)
+    fn south(&mut self, distance: i64) -> Option<()> {
+        self.shift(0, distance.checked_neg()?)
     }
 
-    fn rotate_around_origin(&mut self, angle: f32) {
-        let old_pos = self.position;
-
-        self.position.x =
-            angle.to_radians().cos() * old_pos.x - angle.to_radians().sin() * old_pos.y;
+    fn west(&mut self, distance: i64) -> Option<()> {
+        self.shift(distance.checked_neg()?, 0)
+    }
 
-        self.position.y =
-            angle.to_radians().sin() * old_pos.x + angle.to_radians().cos() * old_pos.y;
+    fn rotate_around_origin(&mut self, quarter_turns: i64) {
+        self.position.rotate(quarter_turns);
     }
 }
 
-#[derive(Debug, Clone)]
-enum Command {
-    NORTH(f32),
-    EAST(f32),
-    SOUTH(f32),
-    WEST(f32),
-    LEFT(f32),
-    RIGHT(f32),
-    FORWARD(f32),
+/// Distances in units, turns in quarter turns.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    North(i64),
+    East(i64),
+    South(i64),
+    West(i64),
+    Left(i64),
+    Right(i64),
+    Forward(i64),
 }
 
 impl Command {
-    fn from(command_str: &str) -> Self {
-        match command_str.split_at(1) {
-            ("N", value) => Self::NORTH(value.parse().unwrap()),
-            ("E", value) => Self::EAST(value.parse().unwrap()),
-            ("S", value) => Self::SOUTH(value.parse().unwrap()),
-            ("W", value) => Self::WEST(value.parse().unwrap()),
-            ("L", value) => Self::LEFT(value.parse().unwrap()),
-            ("R", value) => Self::RIGHT(value.parse().unwrap()),
-            ("F", value) => Self::FORWARD(value.parse().unwrap()),
-            _ => unreachable!(),
+    fn parse(source: &Source, command_str: &str) -> Result<Self, ParseError> {
+        let split = command_str
+            .char_indices()
+            .nth(1)
+            .map_or(command_str.len(), |(index, _)| index);
+        let (action, value) = command_str.split_at(split);
+        let distance = || source.parse(value, "a distance");
+        let quarter_turns = || match source.parse::<i64>(value, "an angle")? {
+            degrees if degrees % 90 == 0 => Ok(degrees / 90),
+            _ => Err(source.error(value, "a multiple of 90 degrees")),
+        };
+        Ok(match action {
+            "N" => Self::North(distance()?),
+            "E" => Self::East(distance()?),
+            "S" => Self::South(distance()?),
+            "W" => Self::West(distance()?),
+            "L" => Self::Left(quarter_turns()?),
+            "R" => Self::Right(quarter_turns()?),
+            "F" => Self::Forward(distance()?),
+            _ => return Err(source.error(action, "one of `NESWLRF`")),
+        })
+    }
+}
+
+#[aoc_generator(day12)]
+pub fn parse_input_day12(input: &str) -> Result<Vec<Command>, ParseError> {
+    let source = Source::new(12, input);
+    input
+        .lines()
+        .map(|line| Command::parse(&source, line))
+        .collect()
+}
+
+/// The part of the plane a track covers, and its point farthest from the
+/// origin by Manhattan distance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+    pub farthest: Point,
+    pub max_distance: i64,
+}
+
+impl Bounds {
+    fn of(points: &[Point]) -> Self {
+        let mut bounds = Bounds {
+            min: points[0],
+            max: points[0],
+            farthest: points[0],
+            max_distance: points[0].manhattan(),
+        };
+        for point in points.iter() {
+            bounds.min = Point::new(bounds.min.x.min(point.x), bounds.min.y.min(point.y));
+            bounds.max = Point::new(bounds.max.x.max(point.x), bounds.max.y.max(point.y));
+            if point.manhattan() > bounds.max_distance {
+                bounds.farthest = *point;
+                bounds.max_distance = point.manhattan();
+            }
         }
+        bounds
     }
 }
 
-fn parse(input: &str) -> Vec<Command> {
-    input.lines().map(|line| Command::from(line)).collect()
+/// Where the ship is before the first command and after each one, and the
+/// same for the waypoint when it sails by one, relative to the origin
+/// rather than the ship.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub ship: Vec<Point>,
+    pub waypoint: Vec<Point>,
 }
 
-#[aoc(day12, part1)]
-fn day12_part1(input: &str) -> Option<isize> {
-    let commands = parse(input);
+impl Route {
+    pub fn end(&self) -> Point {
+        self.ship[self.ship.len() - 1]
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        Bounds::of(&self.ship)
+    }
+
+    /// Both tracks as polylines, north up, with the farthest point of the
+    /// ship's track marked.
+    pub fn svg(&self) -> String {
+        let view = Bounds::of(&[self.ship.as_slice(), self.waypoint.as_slice()].concat());
+        // Tracks can span more than an i64 holds from one side to the other
+        let (left, right) = (i128::from(view.min.x), i128::from(view.max.x));
+        let (bottom, top) = (i128::from(view.min.y), i128::from(view.max.y));
+        let size = (right - left).max(top - bottom).max(1);
+        let margin = size / 20 + 1;
+        let polyline = |points: &[Point], colour: &str| {
+            let points = points
+                .iter()
+                .map(|point| format!("{},{}", point.x, -point.y))
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!(
+                "  <polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1\" \
+                 vector-effect=\"non-scaling-stroke\" points=\"{}\"/>\n",
+                colour, points
+            )
+        };
+
+        let bounds = self.bounds();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+            left - margin,
+            -top - margin,
+            right - left + 2 * margin,
+            top - bottom + 2 * margin
+        );
+        svg.push_str(&format!(
+            "  <title>farthest point ({}, {}), {} from the origin</title>\n",
+            bounds.farthest.x, bounds.farthest.y, bounds.max_distance
+        ));
+        if !self.waypoint.is_empty() {
+            svg.push_str(&polyline(&self.waypoint, "orange"));
+        }
+        svg.push_str(&polyline(&self.ship, "steelblue"));
+        svg.push_str(&format!(
+            "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"red\"/>\n</svg>\n",
+            bounds.farthest.x,
+            -bounds.farthest.y,
+            (size / 100).max(1)
+        ));
+        svg
+    }
+}
+
+/// Command `command` takes the ship or the waypoint so far out that its
+/// Manhattan distance doesn't fit in an `i64`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Overflow {
+    pub command: usize,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "command {} sails past what an i64 holds",
+            self.command + 1
+        )
+    }
+}
+
+impl Error for Overflow {}
+
+pub fn sail(commands: &[Command]) -> Result<Route, Overflow> {
     let mut vector = Vector::origin();
-    for command in commands.iter() {
+    let mut route = Route {
+        ship: vec![vector.position],
+        waypoint: Vec::new(),
+    };
+    for (index, command) in commands.iter().enumerate() {
         match command {
-            Command::NORTH(value) => vector.north(*value),
-            Command::EAST(value) => vector.east(*value),
-            Command::SOUTH(value) => vector.south(*value),
-            Command::WEST(value) => vector.west(*value),
+            Command::North(value) => vector.north(*value),
+            Command::East(value) => vector.east(*value),
+            Command::South(value) => vector.south(*value),
+            Command::West(value) => vector.west(*value),
 
-            Command::LEFT(value) => vector.rotate(*value),
-            Command::RIGHT(value) => vector.rotate(-*value),
+            Command::Left(value) => {
+                vector.rotate(*value);
+                Some(())
+            }
+            Command::Right(value) => {
+                vector.rotate(-*value);
+                Some(())
+            }
 
-            Command::FORWARD(value) => vector.forward(*value),
+            Command::Forward(value) => vector.forward(*value),
         }
+        .ok_or(Overflow { command: index })?;
+        route.ship.push(vector.position);
     }
-    Some(vector.position.manhattan().round() as isize)
+    Ok(route)
 }
 
-#[aoc(day12, part2)]
-fn day12_part2(input: &str) -> Option<isize> {
-    let commands = parse(input);
+pub fn sail_by_waypoint(commands: &[Command]) -> Result<Route, Overflow> {
     let mut ship = Vector::origin();
-    let mut waypoint = Vector::new(Point::new(10_f32, 1_f32));
+    let mut waypoint = Vector::new(Point::new(10, 1));
+    let mut route = Route {
+        ship: vec![ship.position],
+        waypoint: vec![waypoint.position],
+    };
 
-    for command in commands.iter() {
+    for (index, command) in commands.iter().enumerate() {
+        let overflow = Overflow { command: index };
         match command {
-            Command::NORTH(value) => waypoint.north(*value),
-            Command::EAST(value) => waypoint.east(*value),
-            Command::SOUTH(value) => waypoint.south(*value),
-            Command::WEST(value) => waypoint.west(*value),
+            Command::North(value) => waypoint.north(*value),
+            Command::East(value) => waypoint.east(*value),
+            Command::South(value) => waypoint.south(*value),
+            Command::West(value) => waypoint.west(*value),
 
-            Command::LEFT(value) => waypoint.rotate_around_origin(*value),
-            Command::RIGHT(value) => waypoint.rotate_around_origin(-*value),
-
-            Command::FORWARD(value) => {
-                ship.position.x += *value * waypoint.position.x;
-                ship.position.y += *value * waypoint.position.y;
+            Command::Left(value) => {
+                waypoint.rotate_around_origin(*value);
+                Some(())
+            }
+            Command::Right(value) => {
+                waypoint.rotate_around_origin(-*value);
+                Some(())
             }
+
+            Command::Forward(value) => waypoint
+                .position
+                .scaled(*value)
+                .and_then(|step| ship.shift(step.x, step.y)),
         }
+        .ok_or(overflow)?;
+        route.ship.push(ship.position);
+        route.waypoint.push(
+            ship.position
+                .offset(waypoint.position.x, waypoint.position.y)
+                .ok_or(overflow)?,
+        );
     }
-    Some(ship.position.manhattan().round() as isize)
+    Ok(route)
+}
+
+#[aoc(day12, part1)]
+fn day12_part1(commands: &[Command]) -> Result<i64, Overflow> {
+    Ok(sail(commands)?.end().manhattan())
+}
+
+#[aoc(day12, part2)]
+fn day12_part2(commands: &[Command]) -> Result<i64, Overflow> {
+    Ok(sail_by_waypoint(commands)?.end().manhattan())
 }
 
 #[cfg(test)]
@@ -163,12 +358,56 @@ mod tests {
     #[test]
     fn test_part1() {
         let input = "F10\nN3\nF7\nR90\nF11";
-        assert_eq!(day12_part1(input), Some(25));
+        assert_eq!(day12_part1(&parse_input_day12(input).unwrap()), Ok(25));
     }
 
     #[test]
     fn test_part2() {
         let input = "F10\nN3\nF7\nR90\nF11";
-        assert_eq!(day12_part2(input), Some(286));
+        assert_eq!(day12_part2(&parse_input_day12(input).unwrap()), Ok(286));
+
+        let route = sail_by_waypoint(&parse_input_day12(input).unwrap()).unwrap();
+        assert_eq!(route.ship[2], Point::new(100, 10));
+        assert_eq!(route.waypoint[2], Point::new(110, 14));
+        assert_eq!(
+            route.bounds(),
+            Bounds {
+                min: Point::new(0, -72),
+                max: Point::new(214, 38),
+                farthest: Point::new(214, -72),
+                max_distance: 286,
+            }
+        );
+        let svg = route.svg();
+        assert!(svg.contains("<title>farthest point (214, -72), 286 from the origin</title>"));
+        assert!(svg.contains("points=\"0,0 100,-10 100,-10 170,-38 170,-38 214,72\""));
+    }
+
+    #[test]
+    fn test_quarter_turns() {
+        let mut point = Point::new(10, 4);
+        point.rotate(-1);
+        assert_eq!(point, Point::new(4, -10));
+        point.rotate(6);
+        assert_eq!(point, Point::new(-4, 10));
+
+        let input = "F10\nL270\nR45";
+        assert_eq!(parse_input_day12(input).unwrap_err().text, "45");
+        assert_eq!(parse_input_day12("L270").unwrap(), vec![Command::Left(3)]);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let far = "F9223372036854775807\nL180\nF9223372036854775807\nF9223372036854775807";
+        let route = sail(&parse_input_day12(far).unwrap()).unwrap();
+        assert_eq!(route.end(), Point::new(-i64::MAX, 0));
+        assert!(route.svg().contains("viewBox=\"-10145709240540253388 "));
+
+        let commands = parse_input_day12("F9223372036854775807\nN1").unwrap();
+        assert_eq!(sail(&commands).unwrap_err(), Overflow { command: 1 });
+        let commands = parse_input_day12("S-9223372036854775808").unwrap();
+        assert_eq!(sail(&commands).unwrap_err(), Overflow { command: 0 });
+        let commands = parse_input_day12("F922337203685477581").unwrap();
+        assert_eq!(sail_by_waypoint(&commands), Err(Overflow { command: 0 }));
     }
 }
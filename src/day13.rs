@@ -1,24 +1,31 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::crt::{self, Congruence, CrtError};
+use crate::parse::{ParseError, Source};
+
 struct Notes {
     timestamp: usize,
     ids: Vec<(usize, usize)>,
 }
 
 #[aoc_generator(day13)]
-fn parse_input_day13(input: &str) -> Notes {
+fn parse_input_day13(input: &str) -> Result<Notes, ParseError> {
+    let source = Source::new(13, input);
     let mut splits = input.splitn(2, '\n');
-    let timestamp = splits.next().unwrap().parse().unwrap();
+    let timestamp = source.parse(splits.next().unwrap(), "a timestamp")?;
     let ids = splits
         .next()
-        .unwrap()
+        .ok_or_else(|| source.end("a line of bus ids"))?
         .split(',')
         .enumerate()
         .filter(|&(_, id)| id != "x")
-        .map(|(index, id)| (index as usize, id.parse().unwrap()))
-        .collect();
+        .map(|(index, id)| match source.parse(id, "a bus id or `x`")? {
+            0 => Err(source.error(id, "a non-zero bus id")),
+            id => Ok((index, id)),
+        })
+        .collect::<Result<_, _>>()?;
 
-    Notes { timestamp, ids }
+    Ok(Notes { timestamp, ids })
 }
 
 #[aoc(day13, part1)]
@@ -35,6 +42,18 @@ fn day13_part1(notes: &Notes) -> Option<usize> {
     Some(best_id * best_diff)
 }
 
+#[aoc(day13, part2)]
+fn day13_part2(notes: &Notes) -> Result<i128, CrtError> {
+    // Bus `id` leaving `offset` minutes after t means t ≡ -offset (mod id)
+    let congruences: Vec<_> = notes
+        .ids
+        .iter()
+        .map(|&(offset, id)| Congruence::new(-(offset as i128), id as i128))
+        .collect();
+
+    crt::solve(&congruences).map(|solution| solution.remainder)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -42,6 +61,28 @@ mod test {
     #[test]
     fn test_part1() {
         let input = "939\n7,13,x,x,59,x,31,19";
-        assert_eq!(day13_part1(&parse_input_day13(input)), Some(295));
+        assert_eq!(day13_part1(&parse_input_day13(input).unwrap()), Some(295));
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = "939\n7,13,x,x,59,x,31,19";
+        assert_eq!(day13_part2(&parse_input_day13(input).unwrap()), Ok(1068781));
+
+        let input = "0\n17,x,13,19";
+        assert_eq!(day13_part2(&parse_input_day13(input).unwrap()), Ok(3417));
+
+        let input = "0\n1789,37,47,1889";
+        assert_eq!(
+            day13_part2(&parse_input_day13(input).unwrap()),
+            Ok(1202161486)
+        );
+
+        // Buses 2 and 4 can't leave an odd number of minutes apart
+        let input = "0\n2,4";
+        assert_eq!(
+            day13_part2(&parse_input_day13(input).unwrap()),
+            Err(CrtError::NoSolution)
+        );
     }
 }
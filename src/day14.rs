@@ -2,7 +2,9 @@ use std::collections::HashMap;
 
 use regex::Regex;
 
-use aoc_runner_derive::aoc;
+use aoc_runner_derive::{aoc, aoc_generator};
+
+use crate::parse::{ParseError, Source};
 
 #[derive(Debug)]
 enum InputLine {
@@ -10,27 +12,118 @@ enum InputLine {
     Write(usize, usize),
 }
 
-fn parse_input_day14(input: &str) -> Vec<InputLine> {
-    let mask_reg = Regex::new(r"mask = (?P<mask>[X10]+)").unwrap();
-    let write_reg = Regex::new(r"mem\[(?P<address>\d+)\] = (?P<value>\d+)").unwrap();
+/// The addresses that match `bits` everywhere but on the `floating` bits,
+/// which can be anything.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Pattern {
+    bits: usize,
+    floating: usize,
+}
+
+impl Pattern {
+    fn new(address: usize, floating: usize) -> Self {
+        Self {
+            bits: address & !floating,
+            floating,
+        }
+    }
+
+    fn len(&self) -> usize {
+        1 << self.floating.count_ones()
+    }
+
+    fn overlaps(&self, other: &Pattern) -> bool {
+        (self.bits ^ other.bits) & !(self.floating | other.floating) == 0
+    }
+
+    /// What is left of `self` once `other` is taken out, as disjoint
+    /// patterns: one per bit floating in `self` but not in `other`, set the
+    /// other way from `other` with the bits before it set the same way.
+    fn subtract(&self, other: &Pattern) -> Vec<Pattern> {
+        if !self.overlaps(other) {
+            return vec![*self];
+        }
+
+        let mut rest = *self;
+        let mut split = self.floating & !other.floating;
+        let mut pieces = Vec::new();
+        while split != 0 {
+            let bit = split & split.wrapping_neg();
+            split &= !bit;
+            rest.floating &= !bit;
+            pieces.push(Pattern {
+                bits: rest.bits | (!other.bits & bit),
+                floating: rest.floating,
+            });
+            rest.bits |= other.bits & bit;
+        }
+        pieces
+    }
+}
+
+/// Writes as patterns of addresses, none of them sharing an address, so
+/// the sum doesn't have to visit every one.
+#[derive(Debug, Default)]
+struct Memory {
+    writes: Vec<(Pattern, usize)>,
+}
+
+impl Memory {
+    fn write(&mut self, pattern: Pattern, value: usize) {
+        self.writes = self
+            .writes
+            .iter()
+            .flat_map(|&(earlier, value)| {
+                earlier
+                    .subtract(&pattern)
+                    .into_iter()
+                    .map(move |piece| (piece, value))
+            })
+            .collect();
+        self.writes.push((pattern, value));
+    }
+
+    fn sum(&self) -> usize {
+        self.writes
+            .iter()
+            .map(|(pattern, value)| pattern.len() * value)
+            .sum()
+    }
+}
+
+/// The bits of a mask that are `bit`.
+fn bits(mask: &str, bit: char) -> usize {
+    mask.chars()
+        .fold(0, |bits, c| bits << 1 | (c == bit) as usize)
+}
+
+#[aoc_generator(day14)]
+fn parse_input_day14(input: &str) -> Result<Vec<InputLine>, ParseError> {
+    let source = Source::new(14, input);
+    let mask_reg = Regex::new(r"^mask = (?P<mask>[X10]{36})$").unwrap();
+    let write_reg = Regex::new(r"^mem\[(?P<address>\d+)\] = (?P<value>\d+)$").unwrap();
     input
         .lines()
         .map(|line| {
-            if mask_reg.is_match(line) {
-                InputLine::Mask(mask_reg.captures_iter(line).last().unwrap()[1].to_owned())
-            } else if write_reg.is_match(line) {
-                let cap = write_reg.captures_iter(line).last().unwrap();
-                InputLine::Write(cap[1].parse().unwrap(), cap[2].parse().unwrap())
+            if let Some(cap) = mask_reg.captures(line) {
+                Ok(InputLine::Mask(cap[1].to_owned()))
+            } else if let Some(cap) = write_reg.captures(line) {
+                Ok(InputLine::Write(
+                    source.parse(cap.get(1).unwrap().as_str(), "an address")?,
+                    source.parse(cap.get(2).unwrap().as_str(), "a value")?,
+                ))
             } else {
-                unreachable!()
+                Err(source.error(
+                    line,
+                    "`mask = <36 of X, 1 or 0>` or `mem[<address>] = <value>`",
+                ))
             }
         })
         .collect()
 }
 
 #[aoc(day14, part1)]
-fn day14_part1(input: &str) -> Option<usize> {
-    let lines = parse_input_day14(input);
+fn day14_part1(lines: &[InputLine]) -> Option<usize> {
     let mut memory: HashMap<usize, usize> = HashMap::new();
     let mut current_mask = &"".to_owned();
     for line in lines.iter() {
@@ -59,60 +152,22 @@ fn day14_part1(input: &str) -> Option<usize> {
 }
 
 #[aoc(day14, part2)]
-fn day14_part2(input: &str) -> Option<usize> {
-    let lines = parse_input_day14(input);
-    let mut memory: HashMap<usize, usize> = HashMap::new();
-    let mut current_mask = &"".to_owned();
+fn day14_part2(lines: &[InputLine]) -> Option<usize> {
+    let mut memory = Memory::default();
+    let (mut ones, mut floating) = (0, 0);
     for line in lines.iter() {
         match line {
             InputLine::Mask(mask) => {
-                current_mask = mask;
+                ones = bits(mask, '1');
+                floating = bits(mask, 'X');
             }
             InputLine::Write(address, value) => {
-                let mut binary: Vec<_> = format!("{:036b}", address).chars().collect();
-                binary = current_mask
-                    .chars()
-                    .enumerate()
-                    .map(|(i, bit)| {
-                        if bit == '1' || bit == 'X' {
-                            bit
-                        } else {
-                            binary[i]
-                        }
-                    })
-                    .collect();
-
-                let mut combinations = Vec::new();
-                combinations.push(binary);
-                while !combinations.is_empty() {
-                    let mut flag = true;
-                    let current = combinations.pop().unwrap();
-                    for (i, bit) in current.iter().enumerate() {
-                        if *bit == 'X' {
-                            let mut _1 = current.clone();
-                            _1[i] = '1';
-                            let mut _0 = current.clone();
-                            _0[i] = '0';
-                            combinations.push(_1);
-                            combinations.push(_0);
-                            flag = false;
-                            break;
-                        }
-                    }
-                    if flag {
-                        *memory
-                            .entry(
-                                usize::from_str_radix(&current.iter().collect::<String>(), 2)
-                                    .unwrap(),
-                            )
-                            .or_default() = *value;
-                    }
-                }
+                memory.write(Pattern::new(address | ones, floating), *value)
             }
         }
     }
 
-    memory.values().sum::<usize>().into()
+    Some(memory.sum())
 }
 
 #[cfg(test)]
@@ -123,12 +178,38 @@ mod test {
     fn test_part1() {
         let input =
             "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X\nmem[8] = 11\nmem[7] = 101\nmem[8] = 0";
-        assert_eq!(day14_part1(input), Some(165))
+        assert_eq!(day14_part1(&parse_input_day14(input).unwrap()), Some(165))
     }
 
     #[test]
     fn test_part2() {
         let input = "mask = 000000000000000000000000000000X1001X\nmem[42] = 100\nmask = 00000000000000000000000000000000X0XX\nmem[26] = 1";
-        assert_eq!(day14_part2(input), Some(208))
+        assert_eq!(day14_part2(&parse_input_day14(input).unwrap()), Some(208))
+    }
+
+    #[test]
+    fn test_patterns() {
+        let all = Pattern::new(0, 0b1111);
+        let some = Pattern::new(0b0100, 0b0011);
+        let pieces = all.subtract(&some);
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces.iter().map(Pattern::len).sum::<usize>(), 12);
+        assert!(pieces.iter().all(|piece| !piece.overlaps(&some)));
+        assert_eq!(some.subtract(&all), vec![]);
+
+        // 2^36 addresses, far too many to write one by one
+        let input = "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX\nmem[0] = 1\nmask = 000000000000000000000000000000000000\nmem[5] = 3";
+        assert_eq!(
+            day14_part2(&parse_input_day14(input).unwrap()),
+            Some((1 << 36) - 1 + 3)
+        );
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let input = "mask = 000000000000000000000000000000X1001X\nmem[42] <- 100";
+        let error = parse_input_day14(input).unwrap_err();
+        assert_eq!((error.day, error.line, error.column), (14, 2, 1));
+        assert_eq!(error.text, "mem[42] <- 100");
     }
 }
@@ -2,11 +2,14 @@ use std::collections::HashMap;
 
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::parse::{ParseError, Source};
+
 #[aoc_generator(day15)]
-fn parse_input_day13(input: &str) -> Vec<usize> {
+fn parse_input_day15(input: &str) -> Result<Vec<usize>, ParseError> {
+    let source = Source::new(15, input);
     input
         .split(',')
-        .map(|number| number.parse().unwrap())
+        .map(|number| source.parse(number, "a starting number"))
         .collect()
 }
 
@@ -28,9 +31,9 @@ fn solver(numbers: &[usize], nth: usize) -> Option<usize> {
         .enumerate()
         .for_each(|(i, &num)| occurrences.entry(num).or_default().push(i));
 
+    let last = *numbers.last()?;
     (numbers.len()..nth)
-        .into_iter()
-        .fold(*numbers.last().unwrap(), |latest, i| {
+        .fold(last, |latest, i| {
             let occ = occurrences.get(&latest).unwrap();
             let latest = if occ.len() == 1 {
                 0
@@ -40,7 +43,6 @@ fn solver(numbers: &[usize], nth: usize) -> Option<usize> {
             occurrences.entry(latest).or_default().push(i);
             latest
         })
-        .clone()
         .into()
 }
 
@@ -51,24 +53,24 @@ mod test {
     #[test]
     fn test_part1() {
         let input = "0,3,6";
-        assert_eq!(day15_part1(&parse_input_day13(input)), Some(436));
+        assert_eq!(day15_part1(&parse_input_day15(input).unwrap()), Some(436));
 
         let input = "1,3,2";
-        assert_eq!(day15_part1(&parse_input_day13(input)), Some(1));
+        assert_eq!(day15_part1(&parse_input_day15(input).unwrap()), Some(1));
 
         let input = "2,1,3";
-        assert_eq!(day15_part1(&parse_input_day13(input)), Some(10));
+        assert_eq!(day15_part1(&parse_input_day15(input).unwrap()), Some(10));
 
         let input = "1,2,3";
-        assert_eq!(day15_part1(&parse_input_day13(input)), Some(27));
+        assert_eq!(day15_part1(&parse_input_day15(input).unwrap()), Some(27));
 
         let input = "2,3,1";
-        assert_eq!(day15_part1(&parse_input_day13(input)), Some(78));
+        assert_eq!(day15_part1(&parse_input_day15(input).unwrap()), Some(78));
 
         let input = "3,2,1";
-        assert_eq!(day15_part1(&parse_input_day13(input)), Some(438));
+        assert_eq!(day15_part1(&parse_input_day15(input).unwrap()), Some(438));
 
         let input = "3,1,2";
-        assert_eq!(day15_part1(&parse_input_day13(input)), Some(1836));
+        assert_eq!(day15_part1(&parse_input_day15(input).unwrap()), Some(1836));
     }
 }
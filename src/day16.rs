@@ -1,5 +1,9 @@
+use std::{collections::HashMap, error::Error, fmt};
+
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::parse::{ParseError, Source};
+
 #[derive(Debug)]
 enum Rule {
     InRange(usize, usize),
@@ -13,16 +17,13 @@ struct Field {
 
 impl Field {
     fn validate(&self, value: usize) -> bool {
-        self.rules
-            .iter()
-            .map(|rule| match rule {
-                Rule::InRange(min, max) => value >= *min && value <= *max,
-            })
-            .any(|e| e)
+        self.rules.iter().any(|rule| match rule {
+            Rule::InRange(min, max) => (*min..=*max).contains(&value),
+        })
     }
 }
 
-struct Input {
+pub struct Input {
     fields: Vec<Field>,
     personal_ticket: Vec<usize>,
     nearby_tickets: Vec<Vec<usize>>,
@@ -32,74 +33,72 @@ impl Input {
     fn validate_ticket(&self, index: usize) -> bool {
         self.nearby_tickets[index]
             .iter()
-            .map(|value| {
-                self.fields
-                    .iter()
-                    .map(|field| field.validate(*value))
-                    .any(|e| e)
-            })
-            .all(|e| e)
+            .all(|value| self.fields.iter().any(|field| field.validate(*value)))
     }
 }
 
 #[aoc_generator(day16)]
-fn parse_input_day16(input: &str) -> Input {
+pub fn parse_input_day16(input: &str) -> Result<Input, ParseError> {
+    let source = Source::new(16, input);
     let mut sections = input.split("\n\n");
-    let fields = sections
-        .next()
-        .unwrap()
+    let mut section = |expected| sections.next().ok_or_else(|| source.end(expected));
+
+    let fields = section("the ticket fields")?
         .lines()
         .map(|line| {
-            let mut parts = line.split(": ");
-
-            Field {
-                name: parts.next().unwrap().to_string(),
-                rules: parts
-                    .next()
-                    .unwrap()
-                    .split(" or ")
-                    .map(|range| {
-                        let mut bounds = range.split('-');
-                        Rule::InRange(
-                            bounds.next().unwrap().parse().unwrap(),
-                            bounds.next().unwrap().parse().unwrap(),
-                        )
-                    })
-                    .collect(),
-            }
+            let mut parts = line.splitn(2, ": ");
+            let name = parts.next().unwrap().to_string();
+            let ranges = parts
+                .next()
+                .ok_or_else(|| source.error(line, "a `name: ranges` field"))?;
+
+            let rules = ranges
+                .split(" or ")
+                .map(|range| {
+                    let mut bounds = range.splitn(2, '-');
+                    match (bounds.next(), bounds.next()) {
+                        (Some(min), Some(max)) => Ok(Rule::InRange(
+                            source.parse(min, "a lower bound")?,
+                            source.parse(max, "an upper bound")?,
+                        )),
+                        _ => Err(source.error(range, "a `min-max` range")),
+                    }
+                })
+                .collect::<Result<_, _>>()?;
+
+            Ok(Field { name, rules })
         })
-        .collect();
+        .collect::<Result<_, _>>()?;
+
+    let parse_ticket = |line: &str| -> Result<Vec<usize>, ParseError> {
+        line.split(',')
+            .map(|value| source.parse(value, "a ticket value"))
+            .collect()
+    };
 
-    let personal_ticket = sections
-        .next()
-        .unwrap()
+    let personal_ticket = section("your ticket")?
         .lines()
-        .skip(1)
-        .map(|line| {
-            line.split(',')
-                .map(|value| value.parse().unwrap())
-                .collect()
-        })
-        .last()
-        .unwrap();
+        .nth(1)
+        .ok_or_else(|| source.end("your ticket"))
+        .and_then(parse_ticket)?;
 
-    let nearby_tickets = sections
-        .next()
-        .unwrap()
+    let nearby_tickets = section("the nearby tickets")?
         .lines()
         .skip(1)
         .map(|line| {
-            line.split(',')
-                .map(|value| value.parse().unwrap())
-                .collect()
+            let ticket = parse_ticket(line)?;
+            if ticket.len() != personal_ticket.len() {
+                return Err(source.error(line, "as many values as your ticket"));
+            }
+            Ok(ticket)
         })
-        .collect();
+        .collect::<Result<_, _>>()?;
 
-    Input {
+    Ok(Input {
         fields,
         personal_ticket,
         nearby_tickets,
-    }
+    })
 }
 
 #[aoc(day16, part1)]
@@ -115,7 +114,7 @@ fn day16_part1(input: &Input) -> Option<usize> {
                         for rule in field.rules.iter() {
                             match rule {
                                 Rule::InRange(min, max) => {
-                                    if value >= min && value <= max {
+                                    if (min..=max).contains(&value) {
                                         return None;
                                     }
                                 }
@@ -130,8 +129,59 @@ fn day16_part1(input: &Input) -> Option<usize> {
         .into()
 }
 
-#[aoc(day16, part2)]
-fn day16_part2(input: &Input) -> Option<usize> {
+#[derive(Debug, PartialEq)]
+pub enum AssignmentError {
+    /// No field is left that fits every value of this column.
+    Impossible { column: usize },
+    /// This column could hold more than one field.
+    Ambiguous { column: usize },
+}
+
+impl fmt::Display for AssignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Impossible { column } => {
+                write!(f, "no field can be assigned to column {}", column)
+            }
+            Self::Ambiguous { column } => {
+                write!(f, "column {} matches more than one field", column)
+            }
+        }
+    }
+}
+
+impl Error for AssignmentError {}
+
+// Kuhn's augmenting path search: tries to give `column` a field, moving
+// already assigned columns to their other candidates when needed.
+// `owners[field]` is the column currently holding `field`.
+fn augment(
+    column: usize,
+    candidates: &[Vec<usize>],
+    owners: &mut [Option<usize>],
+    visited: &mut [bool],
+    banned: Option<(usize, usize)>,
+) -> bool {
+    for &field in candidates[column].iter() {
+        if visited[field] || banned == Some((column, field)) {
+            continue;
+        }
+        visited[field] = true;
+        let free = match owners[field] {
+            None => true,
+            Some(owner) => augment(owner, candidates, owners, visited, banned),
+        };
+        if free {
+            owners[field] = Some(column);
+            return true;
+        }
+    }
+    false
+}
+
+/// Matches every ticket column to the only field that can describe it,
+/// as a `column -> field name` map.
+pub fn assign_fields(input: &Input) -> Result<HashMap<usize, String>, AssignmentError> {
     let valid_tickets = input
         .nearby_tickets
         .iter()
@@ -145,48 +195,64 @@ fn day16_part2(input: &Input) -> Option<usize> {
         })
         .collect::<Vec<_>>();
 
-    let mut last_solutions = Vec::with_capacity(valid_tickets[0].len());
-    let mut found_fields = Vec::with_capacity(valid_tickets[0].len());
-    let mut indexes: Vec<usize> = (0..valid_tickets[0].len()).collect();
-
-    while !indexes.is_empty() {
-        let column_index = indexes.remove(0);
+    // candidates[column] = fields valid for every value in that column
+    let candidates: Vec<Vec<usize>> = (0..input.personal_ticket.len())
+        .map(|column| {
+            input
+                .fields
+                .iter()
+                .enumerate()
+                .filter(|(_, field)| {
+                    valid_tickets
+                        .iter()
+                        .all(|ticket| field.validate(ticket[column]))
+                })
+                .map(|(index, _)| index)
+                .collect()
+        })
+        .collect();
 
-        let mut column: Vec<_> = valid_tickets
-            .iter()
-            .map(|&ticket| ticket[column_index])
-            .collect();
-        column.sort_unstable();
-        let mut solutions = Vec::new();
+    let mut owners = vec![None; input.fields.len()];
+    for column in 0..candidates.len() {
+        let mut visited = vec![false; input.fields.len()];
+        if !augment(column, &candidates, &mut owners, &mut visited, None) {
+            return Err(AssignmentError::Impossible { column });
+        }
+    }
 
-        'fields: for field in input
-            .fields
-            .iter()
-            .filter(|&field| !found_fields.contains(&field.name))
-        {
-            for value in column.iter() {
-                if field.validate(*value) {
-                    continue;
-                } else {
-                    continue 'fields;
-                }
+    // The assignment is unique only if no column can be given
+    // another field once its current one is taken away.
+    for (field, owner) in owners.iter().enumerate() {
+        if let Some(column) = *owner {
+            let mut alternative = owners.clone();
+            alternative[field] = None;
+            let mut visited = vec![false; input.fields.len()];
+            if augment(
+                column,
+                &candidates,
+                &mut alternative,
+                &mut visited,
+                Some((column, field)),
+            ) {
+                return Err(AssignmentError::Ambiguous { column });
             }
-            solutions.push((column_index, field.name.clone()));
-        }
-        if solutions.len() == 1 {
-            last_solutions.push(solutions[0].clone());
-            found_fields.push(solutions[0].1.clone());
-        } else {
-            // Try later when there is only one solution
-            indexes.push(column_index);
         }
     }
 
-    last_solutions
+    Ok(owners
+        .iter()
+        .enumerate()
+        .filter_map(|(field, owner)| owner.map(|column| (column, input.fields[field].name.clone())))
+        .collect())
+}
+
+#[aoc(day16, part2)]
+fn day16_part2(input: &Input) -> Result<usize, AssignmentError> {
+    Ok(assign_fields(input)?
         .iter()
         .filter(|(_, name)| name.starts_with("departure"))
-        .fold(1, |acc, (i, _)| input.personal_ticket[*i] * acc)
-        .into()
+        .map(|(&column, _)| input.personal_ticket[column])
+        .product())
 }
 
 #[cfg(test)]
@@ -196,6 +262,24 @@ mod test {
     #[test]
     fn test_part1() {
         let input = "class: 1-3 or 5-7\nrow: 6-11 or 33-44\nseat: 13-40 or 45-50\n\nyour ticket:\n7,1,14\n\nnearby tickets:\n7,3,47\n40,4,50\n55,2,20\n38,6,12";
-        assert_eq!(day16_part1(&parse_input_day16(input)), Some(71));
+        assert_eq!(day16_part1(&parse_input_day16(input).unwrap()), Some(71));
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = "class: 0-1 or 4-19\nrow: 0-5 or 8-19\nseat: 0-13 or 16-19\n\nyour ticket:\n11,12,13\n\nnearby tickets:\n3,9,18\n15,1,5\n5,14,9";
+        let input = parse_input_day16(input).unwrap();
+        let expected = vec![(0, "row"), (1, "class"), (2, "seat")]
+            .into_iter()
+            .map(|(column, name)| (column, name.to_string()))
+            .collect();
+        assert_eq!(assign_fields(&input), Ok(expected));
+        assert_eq!(day16_part2(&input), Ok(1));
+
+        let input = "class: 0-1 or 4-19\nrow: 0-5 or 8-19\nseat: 0-13 or 16-19\n\nyour ticket:\n11,12,13\n\nnearby tickets:\n3,9,18";
+        assert!(matches!(
+            assign_fields(&parse_input_day16(input).unwrap()),
+            Err(AssignmentError::Ambiguous { .. })
+        ));
     }
 }
@@ -1,159 +1,97 @@
-use aoc_runner_derive::aoc;
-use std::{collections::HashMap, iter::FromIterator};
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum State {
+use aoc_runner_derive::{aoc, aoc_generator};
+use std::fmt;
+
+use crate::{
+    grid::Grid,
+    life::{Life, MirroredSpace, Rule},
+    parse::{ParseError, Source},
+    render::Frame,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum State {
     Active,
+    #[default]
     Inactive,
 }
 
-impl Default for State {
-    fn default() -> Self {
-        State::Inactive
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct Point {
-    x: isize,
-    y: isize,
-    z: isize,
-    w: isize,
-}
-
-impl Point {
-    fn new(x: isize, y: isize, z: isize, w: isize) -> Self {
-        Point { x, y, z, w }
-    }
-
-    fn neighbors(&self) -> Vec<Point> {
-        let mut vec = Vec::with_capacity(26);
-        for x in (-1..=1).into_iter() {
-            for y in (-1..=1).into_iter() {
-                for z in (-1..=1).into_iter() {
-                    for w in (-1..=1).into_iter() {
-                        if x == 0 && y == 0 && z == 0 && w == 0 {
-                            continue;
-                        }
-                        vec.push(Point::new(self.x + x, self.y + y, self.z + z, self.w + w));
-                    }
-                }
-            }
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            State::Active => write!(f, "#"),
+            State::Inactive => write!(f, "."),
         }
-        vec
     }
 }
 
-#[derive(Debug, Default)]
-struct NeighborsCount {
-    active: usize,
-    inactive: usize,
+/// Places the initial slice at the origin of `N`-dimensional space, its
+/// rows and columns along the first two axes. Being flat along the others,
+/// the pocket dimension stays mirrored around the slice there.
+fn pocket_dimension<const N: usize>(slice: &Grid<State>) -> Life<MirroredSpace<N>> {
+    let active = slice
+        .positions()
+        .filter(|&position| slice[position] == State::Active)
+        .map(|(x, y)| {
+            let mut cube = [0; N];
+            cube[0] = x as isize;
+            cube[1] = y as isize;
+            cube
+        });
+    Life::new(MirroredSpace, Rule::conway(), active)
 }
 
-#[derive(Default, Debug)]
-struct Grid {
-    grid: HashMap<Point, State>,
+fn frame<const N: usize>(pocket: &Life<MirroredSpace<N>>) -> Frame {
+    let title = format!("After {} cycles", pocket.generation());
+    pocket
+        .slices()
+        .into_iter()
+        .fold(Frame::new(title), |frame, (coordinates, slice)| {
+            let label = ["z", "w"]
+                .iter()
+                .zip(coordinates.iter())
+                .map(|(axis, value)| format!("{}={}", axis, value))
+                .collect::<Vec<_>>()
+                .join(", ");
+            frame.slice(label, slice.map(|&active| if active { '#' } else { '.' }))
+        })
 }
 
-impl Grid {
-    fn set(&mut self, point: &Point, state: State) {
-        *self.grid.entry(point.clone()).or_default() = state;
-    }
-
-    fn run(&mut self, n: usize) {
-        for _ in 0..n {
-            self.step();
-        }
-    }
-
-    fn step(&mut self) {
-        let mut new_grid = self.grid.clone();
-        self.build_new_layer(&new_grid);
-        self.grid.iter().for_each(|(point, state)| {
-            let counts = self.get_neighbors_states_count(point);
-
-            if *state == State::Active && counts.active != 2 && counts.active != 3 {
-                *new_grid.entry(point.clone()).or_default() = State::Inactive;
-            }
-
-            if *state == State::Inactive && counts.active == 3 {
-                *new_grid.entry(point.clone()).or_default() = State::Active;
-            }
-        });
-
-        self.grid = new_grid;
-    }
-
-    fn build_new_layer(&mut self, new_board: &HashMap<Point, State>) {
-        new_board.iter().for_each(|(point, _)| {
-            point.neighbors().iter().for_each(|neighbor| {
-                self.grid.entry(neighbor.clone()).or_default();
-            });
-        });
-    }
-
-    fn get_neighbors_states_count(&self, point: &Point) -> NeighborsCount {
-        let mut count = NeighborsCount::default();
-        point.neighbors().iter().for_each(|neighbor| {
-            match self.grid.get(neighbor).unwrap_or(&State::Inactive) {
-                State::Active => count.active += 1,
-                State::Inactive => count.inactive += 1,
-            }
-        });
-        count
-    }
-
-    fn count_alive(&self) -> usize {
-        self.grid
-            .iter()
-            .filter(|&(_, state)| *state == State::Active)
-            .count()
+/// The pocket dimension after each of the first `cycles` cycles, for
+/// `Renderer::write`.
+pub fn frames<const N: usize>(slice: &Grid<State>, cycles: usize) -> Vec<Frame> {
+    let mut pocket = pocket_dimension::<N>(slice);
+    let mut frames = vec![frame(&pocket)];
+    for _ in 0..cycles {
+        pocket.step();
+        frames.push(frame(&pocket));
     }
+    frames
 }
 
-impl FromIterator<Vec<State>> for Grid {
-    fn from_iter<T: IntoIterator<Item = Vec<State>>>(iter: T) -> Self {
-        let initial = iter.into_iter().collect::<Vec<_>>();
-
-        let mut grid = Self::default();
-        initial.iter().enumerate().for_each(|(x, row)| {
-            row.iter().enumerate().for_each(|(y, cell)| {
-                let point = Point::new(x as isize, y as isize, 0, 0);
-                grid.set(&point, *cell);
-            })
-        });
-
-        grid
-    }
+fn boot<const N: usize>(slice: &Grid<State>) -> usize {
+    let mut pocket = pocket_dimension::<N>(slice);
+    pocket.run(6);
+    pocket.population()
 }
 
-fn parse_input_day17(input: &str) -> Grid {
-    input
-        .lines()
-        .map(|line| {
-            line.chars()
-                .map(|cell| match cell {
-                    '#' => State::Active,
-                    '.' => State::Inactive,
-                    _ => unreachable!(),
-                })
-                .collect::<Vec<_>>()
-        })
-        .collect()
+#[aoc_generator(day17)]
+pub fn parse_input_day17(input: &str) -> Result<Grid<State>, ParseError> {
+    let source = Source::new(17, input);
+    Grid::parse(&source, input, "`#` or `.`", |cell| match cell {
+        '#' => Some(State::Active),
+        '.' => Some(State::Inactive),
+        _ => None,
+    })
 }
 
 #[aoc(day17, part1)]
-fn day17_part1(input: &str) -> Option<usize> {
-    let mut grid = parse_input_day17(input);
-    grid.run(6);
-    Some(grid.count_alive())
+fn day17_part1(slice: &Grid<State>) -> Option<usize> {
+    Some(boot::<3>(slice))
 }
 
 #[aoc(day17, part2)]
-fn day17_part2(input: &str) -> Option<usize> {
-    let mut grid = parse_input_day17(input);
-    grid.run(6);
-    Some(grid.count_alive())
+fn day17_part2(slice: &Grid<State>) -> Option<usize> {
+    Some(boot::<4>(slice))
 }
 
 #[cfg(test)]
@@ -161,16 +99,27 @@ mod tests {
     use super::*;
 
     #[test]
-    #[ignore]
     fn test_part1() {
         let input = ".#.\n..#\n###";
-        assert_eq!(day17_part1(input), Some(112));
+        assert_eq!(day17_part1(&parse_input_day17(input).unwrap()), Some(112));
+
+        let frames = frames::<3>(&parse_input_day17(input).unwrap(), 1);
+        assert_eq!(frames[1].title, "After 1 cycles");
+        let text = frames[1]
+            .slices
+            .iter()
+            .map(|(label, slice)| format!("{}\n{}", label, slice))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            text,
+            "z=-1\n#..\n..#\n.#.\n\nz=0\n#.#\n.##\n.#.\n\nz=1\n#..\n..#\n.#.\n"
+        );
     }
 
     #[test]
-    #[ignore]
     fn test_part2() {
         let input = ".#.\n..#\n###";
-        assert_eq!(day17_part2(input), Some(848));
+        assert_eq!(day17_part2(&parse_input_day17(input).unwrap()), Some(848));
     }
 }
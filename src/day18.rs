@@ -1,141 +1,447 @@
-use aoc_runner_derive::aoc;
+use std::{collections::HashMap, error::Error, fmt};
 
-#[derive(Debug, Copy, Clone)]
-enum Token {
+use aoc_runner_derive::{aoc, aoc_generator};
+
+use crate::parse::{ParseError, Source};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Operator {
     Add,
+    Subtract,
     Multiply,
-    LeftPrentices,
-    RightPrentices,
-    Number(usize),
+    Divide,
+    Remainder,
 }
 
-fn parse_input_day18(input: &str) -> Vec<Vec<Token>> {
-    input
-        .lines()
-        .map(|line| {
-            line.replace(" ", "")
-                .chars()
-                .map(|token| match token {
-                    '+' => Token::Add,
-                    '*' => Token::Multiply,
-                    '(' => Token::LeftPrentices,
-                    ')' => Token::RightPrentices,
-                    number => Token::Number(number.to_string().parse().unwrap()),
-                })
-                .collect()
-        })
-        .collect()
+impl Operator {
+    fn apply(self, left: i64, right: i64) -> Result<i64, Fault> {
+        if right == 0 && matches!(self, Operator::Divide | Operator::Remainder) {
+            return Err(Fault::DivisionByZero);
+        }
+        match self {
+            Operator::Add => left.checked_add(right),
+            Operator::Subtract => left.checked_sub(right),
+            Operator::Multiply => left.checked_mul(right),
+            Operator::Divide => left.checked_div(right),
+            Operator::Remainder => left.checked_rem(right),
+        }
+        .ok_or(Fault::Overflow)
+    }
 }
 
-trait Solver {
-    fn evaluate(&self, expr: &mut Vec<&Token>) -> usize;
-    fn factor(&self, expr: &mut Vec<&Token>) -> usize;
-    fn term(&self, _: &mut Vec<&Token>) -> usize {
-        unreachable!();
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Operator::Add => '+',
+            Operator::Subtract => '-',
+            Operator::Multiply => '*',
+            Operator::Divide => '/',
+            Operator::Remainder => '%',
+        };
+        write!(f, "{}", symbol)
     }
 }
 
-struct SolverPartOne;
-impl Solver for SolverPartOne {
-    fn evaluate(&self, mut expr: &mut Vec<&Token>) -> usize {
-        let mut result = self.factor(&mut expr);
-        while !expr.is_empty() {
-            match expr.pop().unwrap() {
-                Token::Add => {
-                    result += self.factor(&mut expr);
-                }
-                Token::Multiply => {
-                    result *= self.factor(&mut expr);
-                }
-                Token::RightPrentices => {
-                    return result;
-                }
-                _ => {
-                    unreachable!();
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Token {
+    Number(i64),
+    Operator(Operator),
+    LeftParenthesis,
+    RightParenthesis,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(i64),
+    Binary(Operator, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn evaluate(&self, precedence: &Precedence) -> Result<i64, EvalError> {
+        self.fold().map_err(|fault| {
+            // Replays the evaluation step by step to show where it failed
+            self.clone()
+                .reductions(precedence)
+                .find_map(Result::err)
+                .unwrap_or_else(|| EvalError::Arithmetic {
+                    fault,
+                    step: self.render(precedence),
+                })
+        })
+    }
+
+    fn fold(&self) -> Result<i64, Fault> {
+        match self {
+            Expr::Number(number) => Ok(*number),
+            Expr::Binary(operator, left, right) => operator.apply(left.fold()?, right.fold()?),
+        }
+    }
+
+    pub fn reductions(self, precedence: &Precedence) -> Reductions<'_> {
+        Reductions {
+            expr: Some(self),
+            precedence,
+            started: false,
+        }
+    }
+
+    /// Replaces the leftmost operation whose operands are both numbers by
+    /// its result, returns false once the whole expression is a number.
+    fn reduce(&mut self) -> Result<bool, Fault> {
+        match self {
+            Expr::Number(_) => Ok(false),
+            Expr::Binary(operator, left, right) => match (&**left, &**right) {
+                (Expr::Number(left), Expr::Number(right)) => {
+                    *self = Expr::Number(operator.apply(*left, *right)?);
+                    Ok(true)
                 }
+                _ => Ok(left.reduce()? || right.reduce()?),
+            },
+        }
+    }
+
+    /// Writes the expression with only the parentheses `precedence` needs.
+    pub fn render(&self, precedence: &Precedence) -> String {
+        match self {
+            Expr::Number(number) => number.to_string(),
+            Expr::Binary(operator, left, right) => {
+                let (level, associativity) = precedence.levels[operator];
+                let operand = |child: &Expr, side: Associativity| match child {
+                    Expr::Binary(inner, ..)
+                        if precedence.levels[inner].0 < level
+                            || (precedence.levels[inner].0 == level && associativity != side) =>
+                    {
+                        format!("({})", child.render(precedence))
+                    }
+                    _ => child.render(precedence),
+                };
+                format!(
+                    "{} {} {}",
+                    operand(left, Associativity::Left),
+                    operator,
+                    operand(right, Associativity::Right)
+                )
             }
         }
-        result
     }
+}
 
-    fn factor(&self, mut expr: &mut Vec<&Token>) -> usize {
-        match expr.pop().unwrap() {
-            Token::Number(number) => *number,
-            Token::LeftPrentices => self.evaluate(&mut expr),
-            _ => {
-                unreachable!();
+/// Writes every operation but the outermost one in parentheses.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operand = |child: &Expr| match child {
+            Expr::Number(_) => child.to_string(),
+            Expr::Binary(..) => format!("({})", child),
+        };
+        match self {
+            Expr::Number(number) => write!(f, "{}", number),
+            Expr::Binary(operator, left, right) => {
+                write!(f, "{} {} {}", operand(left), operator, operand(right))
             }
         }
     }
 }
 
-struct SolverPartTwo;
-impl Solver for SolverPartTwo {
-    fn evaluate(&self, mut expr: &mut Vec<&Token>) -> usize {
-        let mut result = self.term(&mut expr);
-        while !expr.is_empty() {
-            match expr.pop().unwrap() {
-                Token::Multiply => {
-                    result *= self.term(&mut expr);
-                }
-                Token::RightPrentices => {
-                    return result;
-                }
-                _ => {
-                    unreachable!();
-                }
+/// Evaluates an expression one operation at a time, yielding it before
+/// the first step and after every step.
+pub struct Reductions<'a> {
+    expr: Option<Expr>,
+    precedence: &'a Precedence,
+    started: bool,
+}
+
+impl Iterator for Reductions<'_> {
+    type Item = Result<Expr, EvalError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let expr = self.expr.as_mut()?;
+        if !std::mem::replace(&mut self.started, true) {
+            return Some(Ok(expr.clone()));
+        }
+        match expr.reduce() {
+            Ok(true) => Some(Ok(expr.clone())),
+            Ok(false) => {
+                self.expr = None;
+                None
+            }
+            Err(fault) => {
+                let step = expr.render(self.precedence);
+                self.expr = None;
+                Some(Err(EvalError::Arithmetic { fault, step }))
             }
         }
-        result
     }
+}
 
-    fn term(&self, mut expr: &mut Vec<&Token>) -> usize {
-        let mut result = self.factor(&mut expr);
-        while !expr.is_empty() {
-            match expr.pop().unwrap() {
-                Token::Add => {
-                    result += self.factor(&mut expr);
-                }
-                other => {
-                    expr.push(other);
-                    return result;
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fault {
+    Overflow,
+    DivisionByZero,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EvalError {
+    /// The line doesn't parse with the chosen precedence table.
+    Syntax(SyntaxError),
+    /// `step` is the expression as it was when `fault` happened.
+    Arithmetic { fault: Fault, step: String },
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(error) => write!(
+                f,
+                "expected {} at token {}",
+                error.expected,
+                error.token + 1
+            ),
+            Self::Arithmetic {
+                fault: Fault::Overflow,
+                step,
+            } => write!(f, "`{}` doesn't fit in 64 bits", step),
+            Self::Arithmetic {
+                fault: Fault::DivisionByZero,
+                step,
+            } => write!(f, "division by zero in `{}`", step),
+        }
+    }
+}
+
+impl Error for EvalError {}
+
+impl From<SyntaxError> for EvalError {
+    fn from(error: SyntaxError) -> Self {
+        EvalError::Syntax(error)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+/// How tightly each operator binds, higher levels bind first.
+/// Operators missing from the table can't be parsed.
+#[derive(Debug, Clone)]
+pub struct Precedence {
+    levels: HashMap<Operator, (u8, Associativity)>,
+}
+
+impl Precedence {
+    pub fn new(levels: &[(Operator, u8, Associativity)]) -> Self {
+        Self {
+            levels: levels
+                .iter()
+                .map(|&(operator, level, associativity)| (operator, (level, associativity)))
+                .collect(),
+        }
+    }
+
+    /// Everything binds the same, left to right.
+    pub fn part1() -> Self {
+        Self::new(&[
+            (Operator::Add, 1, Associativity::Left),
+            (Operator::Subtract, 1, Associativity::Left),
+            (Operator::Multiply, 1, Associativity::Left),
+            (Operator::Divide, 1, Associativity::Left),
+            (Operator::Remainder, 1, Associativity::Left),
+        ])
+    }
+
+    /// Addition and subtraction bind before everything else.
+    pub fn part2() -> Self {
+        Self::new(&[
+            (Operator::Add, 2, Associativity::Left),
+            (Operator::Subtract, 2, Associativity::Left),
+            (Operator::Multiply, 1, Associativity::Left),
+            (Operator::Divide, 1, Associativity::Left),
+            (Operator::Remainder, 1, Associativity::Left),
+        ])
+    }
+
+    pub fn standard() -> Self {
+        Self::new(&[
+            (Operator::Add, 1, Associativity::Left),
+            (Operator::Subtract, 1, Associativity::Left),
+            (Operator::Multiply, 2, Associativity::Left),
+            (Operator::Divide, 2, Associativity::Left),
+            (Operator::Remainder, 2, Associativity::Left),
+        ])
+    }
+}
+
+/// Where parsing a line of tokens failed, `token` is the index of the
+/// offending token, or the number of tokens if the line ended too early.
+#[derive(Debug, PartialEq)]
+pub struct SyntaxError {
+    pub token: usize,
+    pub expected: &'static str,
+}
+
+/// A Pratt parser over one line of tokens.
+pub struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    precedence: &'a Precedence,
+}
+
+impl<'a> Parser<'a> {
+    pub fn parse(tokens: &'a [Token], precedence: &'a Precedence) -> Result<Expr, SyntaxError> {
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            precedence,
+        };
+        let expr = parser.expression(0)?;
+        if parser.position < tokens.len() {
+            return Err(parser.error("an operator"));
+        }
+        Ok(expr)
+    }
+
+    fn error(&self, expected: &'static str) -> SyntaxError {
+        SyntaxError {
+            token: self.position,
+            expected,
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).copied();
+        self.position += 1;
+        token
+    }
+
+    fn operand(&mut self) -> Result<Expr, SyntaxError> {
+        match self.tokens.get(self.position) {
+            Some(Token::Number(number)) => {
+                self.position += 1;
+                Ok(Expr::Number(*number))
+            }
+            Some(Token::LeftParenthesis) => {
+                self.position += 1;
+                let expr = self.expression(0)?;
+                match self.tokens.get(self.position) {
+                    Some(Token::RightParenthesis) => {
+                        self.position += 1;
+                        Ok(expr)
+                    }
+                    _ => Err(self.error("an operator or `)`")),
                 }
             }
+            _ => Err(self.error("a number or `(`")),
         }
-        result
     }
 
-    fn factor(&self, mut expr: &mut Vec<&Token>) -> usize {
-        match expr.pop().unwrap() {
-            Token::Number(number) => *number,
-            Token::LeftPrentices => self.evaluate(&mut expr),
-            _ => {
-                unreachable!();
+    /// Parses operators binding at least as tight as `min_level`, which is
+    /// wider than the table's levels so that one past 255 still fits.
+    fn expression(&mut self, min_level: u16) -> Result<Expr, SyntaxError> {
+        let mut left = self.operand()?;
+        while let Some(&Token::Operator(operator)) = self.tokens.get(self.position) {
+            let &(level, associativity) = self
+                .precedence
+                .levels
+                .get(&operator)
+                .ok_or_else(|| self.error("an operator from the precedence table"))?;
+            let level = u16::from(level);
+            if level < min_level {
+                break;
             }
+            self.next();
+
+            let next_level = match associativity {
+                Associativity::Left => level + 1,
+                Associativity::Right => level,
+            };
+            let right = self.expression(next_level)?;
+            left = Expr::Binary(operator, Box::new(left), Box::new(right));
         }
+        Ok(left)
     }
 }
 
-fn evaluate(solver: impl Solver, input: &str) -> Option<usize> {
-    let mut expressions = parse_input_day18(input);
-    expressions
-        .iter_mut()
-        .map(|expr| {
-            let mut expr = expr.iter().rev().collect::<Vec<_>>();
-            solver.evaluate(&mut expr)
+fn tokenize<'a>(source: &Source, line: &'a str) -> Result<Vec<(Token, &'a str)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Operator(Operator::Add),
+            '-' => Token::Operator(Operator::Subtract),
+            '*' => Token::Operator(Operator::Multiply),
+            '/' => Token::Operator(Operator::Divide),
+            '%' => Token::Operator(Operator::Remainder),
+            '(' => Token::LeftParenthesis,
+            ')' => Token::RightParenthesis,
+            c if c.is_ascii_digit() => {
+                let mut end = start + 1;
+                while let Some(&(index, c)) = chars.peek() {
+                    if !c.is_ascii_digit() {
+                        break;
+                    }
+                    end = index + 1;
+                    chars.next();
+                }
+                tokens.push((
+                    Token::Number(source.parse(&line[start..end], "a number")?),
+                    &line[start..end],
+                ));
+                continue;
+            }
+            c => {
+                return Err(source.error(
+                    &line[start..start + c.len_utf8()],
+                    "a number, an operator or a parenthesis",
+                ))
+            }
+        };
+        tokens.push((token, &line[start..start + c.len_utf8()]));
+    }
+    Ok(tokens)
+}
+
+/// Tokenizes every line and checks it parses. Tables order operators
+/// differently and so build different trees, but any table that has every
+/// operator accepts the same lines, so the standard one is used to check.
+#[aoc_generator(day18)]
+pub fn parse_input_day18(input: &str) -> Result<Vec<Vec<Token>>, ParseError> {
+    let source = Source::new(18, input);
+    input
+        .lines()
+        .map(|line| {
+            let (tokens, texts): (Vec<_>, Vec<_>) = tokenize(&source, line)?.into_iter().unzip();
+            Parser::parse(&tokens, &Precedence::standard()).map_err(|error| {
+                let text = texts
+                    .get(error.token)
+                    .copied()
+                    .unwrap_or(&line[line.len()..]);
+                source.error(text, error.expected)
+            })?;
+            Ok(tokens)
         })
-        .sum::<usize>()
-        .into()
+        .collect()
+}
+
+fn evaluate(expressions: &[Vec<Token>], precedence: &Precedence) -> Result<i64, EvalError> {
+    expressions.iter().try_fold(0_i64, |sum, tokens| {
+        let value = Parser::parse(tokens, precedence)?.evaluate(precedence)?;
+        sum.checked_add(value).ok_or_else(|| EvalError::Arithmetic {
+            fault: Fault::Overflow,
+            step: format!("{} + {}", sum, value),
+        })
+    })
 }
 
 #[aoc(day18, part1)]
-fn day18_part1(input: &str) -> Option<usize> {
-    evaluate(SolverPartOne {}, input)
+fn day18_part1(expressions: &[Vec<Token>]) -> Result<i64, EvalError> {
+    evaluate(expressions, &Precedence::part1())
 }
 
 #[aoc(day18, part2)]
-fn day18_part2(input: &str) -> Option<usize> {
-    evaluate(SolverPartTwo {}, input)
+fn day18_part2(expressions: &[Vec<Token>]) -> Result<i64, EvalError> {
+    evaluate(expressions, &Precedence::part2())
 }
 
 #[cfg(test)]
@@ -145,36 +451,123 @@ mod tests {
     #[test]
     fn test_part1() {
         let input = "1 + 2 * 3 + 4 * 5 + 6";
-        assert_eq!(day18_part1(input), Some(71));
+        assert_eq!(day18_part1(&parse_input_day18(input).unwrap()), Ok(71));
 
         let input = "2 * 3 + (4 * 5)";
-        assert_eq!(day18_part1(input), Some(26));
+        assert_eq!(day18_part1(&parse_input_day18(input).unwrap()), Ok(26));
 
         let input = "5 + (8 * 3 + 9 + 3 * 4 * 3)";
-        assert_eq!(day18_part1(input), Some(437));
+        assert_eq!(day18_part1(&parse_input_day18(input).unwrap()), Ok(437));
 
         let input = "5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))";
-        assert_eq!(day18_part1(input), Some(12240));
+        assert_eq!(day18_part1(&parse_input_day18(input).unwrap()), Ok(12240));
 
         let input = "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2";
-        assert_eq!(day18_part1(input), Some(13632));
+        assert_eq!(day18_part1(&parse_input_day18(input).unwrap()), Ok(13632));
     }
 
     #[test]
     fn test_part2() {
         let input = "1 + 2 * 3 + 4 * 5 + 6";
-        assert_eq!(day18_part2(input), Some(231));
+        assert_eq!(day18_part2(&parse_input_day18(input).unwrap()), Ok(231));
 
         let input = "2 * 3 + (4 * 5)";
-        assert_eq!(day18_part2(input), Some(46));
+        assert_eq!(day18_part2(&parse_input_day18(input).unwrap()), Ok(46));
 
         let input = "5 + (8 * 3 + 9 + 3 * 4 * 3)";
-        assert_eq!(day18_part2(input), Some(1445));
+        assert_eq!(day18_part2(&parse_input_day18(input).unwrap()), Ok(1445));
 
         let input = "5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))";
-        assert_eq!(day18_part2(input), Some(669060));
+        assert_eq!(day18_part2(&parse_input_day18(input).unwrap()), Ok(669060));
 
         let input = "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2";
-        assert_eq!(day18_part2(input), Some(23340));
+        assert_eq!(day18_part2(&parse_input_day18(input).unwrap()), Ok(23340));
+    }
+
+    #[test]
+    fn test_precedence() {
+        let input = "12 + 3 * 10";
+        let expressions = parse_input_day18(input).unwrap();
+        assert_eq!(evaluate(&expressions, &Precedence::part1()), Ok(150));
+        assert_eq!(evaluate(&expressions, &Precedence::standard()), Ok(42));
+
+        let right = Precedence::new(&[(Operator::Add, 1, Associativity::Right)]);
+        let tokens = &parse_input_day18("1 + 2 + 3").unwrap()[0];
+        assert_eq!(
+            Parser::parse(tokens, &right),
+            Ok(Expr::Binary(
+                Operator::Add,
+                Box::new(Expr::Number(1)),
+                Box::new(Expr::Binary(
+                    Operator::Add,
+                    Box::new(Expr::Number(2)),
+                    Box::new(Expr::Number(3))
+                ))
+            ))
+        );
+
+        // The highest level a table can hold still binds left to right
+        let highest = Precedence::new(&[
+            (Operator::Subtract, u8::MAX, Associativity::Left),
+            (Operator::Multiply, 1, Associativity::Left),
+        ]);
+        let expressions = parse_input_day18("2 * 10 - 3 - 4").unwrap();
+        assert_eq!(evaluate(&expressions, &highest), Ok(6));
+
+        let error = parse_input_day18("(1 + 2").unwrap_err();
+        assert_eq!((error.column, error.expected), (7, "an operator or `)`"));
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let input = "100 - 7 % 4 * 2 / 3\n(2 - 10) / 4";
+        let expressions = parse_input_day18(input).unwrap();
+        assert_eq!(evaluate(&expressions, &Precedence::standard()), Ok(96));
+        assert_eq!(evaluate(&expressions, &Precedence::part1()), Ok(-2));
+
+        let expressions = parse_input_day18("1 + 7 % (3 - 3)").unwrap();
+        assert_eq!(
+            evaluate(&expressions, &Precedence::standard()),
+            Err(EvalError::Arithmetic {
+                fault: Fault::DivisionByZero,
+                step: "1 + 7 % 0".to_string()
+            })
+        );
+
+        let expressions = parse_input_day18("9223372036854775807\n1").unwrap();
+        assert_eq!(
+            evaluate(&expressions, &Precedence::standard()),
+            Err(EvalError::Arithmetic {
+                fault: Fault::Overflow,
+                step: "9223372036854775807 + 1".to_string()
+            })
+        );
+
+        let error = parse_input_day18("2 ^ 3").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 3));
+    }
+
+    #[test]
+    fn test_printing_and_reductions() {
+        let tokens = &parse_input_day18("2 * 3 + (4 * 5)").unwrap()[0];
+        let expr = Parser::parse(tokens, &Precedence::part1()).unwrap();
+        assert_eq!(expr.to_string(), "(2 * 3) + (4 * 5)");
+
+        let precedence = Precedence::part2();
+        let expr = Parser::parse(tokens, &precedence).unwrap();
+        assert_eq!(expr.to_string(), "2 * (3 + (4 * 5))");
+        let steps = expr
+            .reductions(&precedence)
+            .map(|step| step.map(|expr| expr.render(&precedence)))
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(
+            steps,
+            Ok(vec![
+                "2 * 3 + (4 * 5)".to_string(),
+                "2 * 3 + 20".to_string(),
+                "2 * 23".to_string(),
+                "46".to_string()
+            ])
+        );
     }
 }
@@ -1,128 +1,218 @@
-use regex::Regex;
+use std::collections::{HashMap, HashSet};
 
-use aoc_runner_derive::aoc;
+use aoc_runner_derive::{aoc, aoc_generator};
 
-const MAX_RULES: usize = 130;
+use crate::parse::{ParseError, Source};
 
-fn parse_input_day19(input: &str) -> (Vec<String>, Vec<String>) {
-    let mut input = input.split("\n\n");
-    let rule_regex = Regex::new(r#"^(\d+): "?([\d\s\|\w]+)"?$"#).unwrap();
-    let raw_rules: Vec<_> = input.next().unwrap().lines().collect();
-    let mut rules = vec!["".to_string(); MAX_RULES];
-    raw_rules.iter().for_each(|line| {
-        let cap = rule_regex.captures_iter(line).next().unwrap();
-        rules[cap[1].parse::<usize>().unwrap()] = cap[2].to_string();
-    });
+#[derive(Debug, Clone)]
+enum Rule {
+    Char(char),
+    Alternatives(Vec<Vec<usize>>),
+}
 
-    let messages = input
-        .next()
-        .unwrap()
-        .lines()
-        .map(|line| line.to_owned())
-        .collect::<Vec<_>>();
+impl Rule {
+    fn parse(source: &Source, body: &str) -> Result<Self, ParseError> {
+        if let Some(quoted) = body.strip_prefix('"') {
+            let mut chars = quoted.chars();
+            match (chars.next(), chars.as_str()) {
+                (Some(c), "\"") => Ok(Rule::Char(c)),
+                _ => Err(source.error(body, "a single quoted character")),
+            }
+        } else {
+            body.split('|')
+                .map(|sequence| {
+                    let rules = sequence
+                        .split_whitespace()
+                        .map(|rule| source.parse(rule, "a rule number"))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if rules.is_empty() {
+                        Err(source.error(sequence, "at least one rule number"))
+                    } else {
+                        Ok(rules)
+                    }
+                })
+                .collect::<Result<_, _>>()
+                .map(Rule::Alternatives)
+        }
+    }
+}
 
-    (rules, messages)
+// An Earley item: `rule -> alternative[..dot] . alternative[dot..]`,
+// started at position `origin` of the message. No rule stands for the
+// extra `goal: start` rule every match is anchored on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Item {
+    rule: Option<usize>,
+    alternative: usize,
+    dot: usize,
+    origin: usize,
 }
 
-fn calculate_regex_for_rule_p1(rules: &[String], index: &usize) -> String {
-    let rule = &rules[*index];
-    if rule.len() == 1 {
-        format!(r#"({})"#, rule)
-    } else {
-        format!(
-            r#"({})"#,
-            rule.split('|')
-                .map(|pat| {
-                    format!(
-                        r#"({})"#,
-                        pat.trim()
-                            .split(' ')
-                            .map(|inner_rule| {
-                                let index = inner_rule.parse::<usize>().unwrap();
-                                calculate_regex_for_rule_p1(rules, &index)
-                            })
-                            .collect::<String>()
-                    )
-                })
-                .collect::<Vec<_>>()
-                .join("|")
-        )
+impl Item {
+    fn advance(self) -> Self {
+        Item {
+            dot: self.dot + 1,
+            ..self
+        }
     }
 }
 
-fn calculate_regex_for_rule_p2(rules: &[String], index: &usize) -> String {
-    let rule = &rules[*index];
-    if rule.len() == 1 {
-        format!(r#"({})"#, rule)
-    } else if *index == 8 {
-        // (8 = 42 | 42 8) == (42)+
-        format!("(({})+)", calculate_regex_for_rule_p2(rules, &42))
-    } else if *index == 11 {
-        // (11 = 42 31 | 42 11 31) == (42){n}(31){n}
-        // Since i didn't find a way to do this in regex
-        // we will assume a maximum number of recursions
-        // and keep trying to reach the number that passes.
-        // Fortunately (5) was correct and worked from first trial :)
-        let forty_two = calculate_regex_for_rule_p2(rules, &42);
-        let thirty_one = calculate_regex_for_rule_p2(rules, &31);
-        format!(
-            "({}({}({}({}({}{})?{})?{})?{})?{})",
-            forty_two,
-            forty_two,
-            forty_two,
-            forty_two,
-            forty_two,
-            thirty_one,
-            thirty_one,
-            thirty_one,
-            thirty_one,
-            thirty_one
-        )
-    } else {
-        format!(
-            r#"({})"#,
-            rule.split('|')
-                .map(|pat| {
-                    format!(
-                        r#"({})"#,
-                        pat.trim()
-                            .split(' ')
-                            .map(|inner_rule| {
-                                let index = inner_rule.parse::<usize>().unwrap();
-                                calculate_regex_for_rule_p2(rules, &index)
-                            })
-                            .collect::<String>()
-                    )
-                })
-                .collect::<Vec<_>>()
-                .join("|")
-        )
+#[derive(Debug, Default, Clone)]
+struct Grammar {
+    rules: HashMap<usize, Rule>,
+}
+
+impl Grammar {
+    fn insert(&mut self, id: usize, rule: Rule) {
+        self.rules.insert(id, rule);
+    }
+
+    fn sequence<'a>(&'a self, start: &'a usize, item: &Item) -> &'a [usize] {
+        match item.rule {
+            None => std::slice::from_ref(start),
+            Some(rule) => match &self.rules[&rule] {
+                Rule::Alternatives(alternatives) => &alternatives[item.alternative],
+                Rule::Char(_) => unreachable!(),
+            },
+        }
+    }
+
+    /// Runs an Earley recognizer, so any recursion, left or right,
+    /// is handled without rewriting the rules first.
+    fn matches(&self, start: usize, message: &str) -> bool {
+        let chars: Vec<char> = message.chars().collect();
+        let mut sets = vec![Vec::new(); chars.len() + 1];
+        let mut seen = vec![HashSet::new(); chars.len() + 1];
+
+        let goal = Item {
+            rule: None,
+            alternative: 0,
+            dot: 0,
+            origin: 0,
+        };
+        sets[0].push(goal);
+        seen[0].insert(goal);
+
+        for position in 0..=chars.len() {
+            let mut index = 0;
+            while index < sets[position].len() {
+                let item = sets[position][index];
+                index += 1;
+
+                let mut found = Vec::new();
+                let mut scanned = None;
+                match self.sequence(&start, &item).get(item.dot) {
+                    // Predict or scan the next symbol
+                    Some(next) => match self.rules.get(next) {
+                        Some(Rule::Char(c)) if chars.get(position) == Some(c) => {
+                            scanned = Some(item.advance());
+                        }
+                        Some(Rule::Alternatives(alternatives)) => {
+                            found.extend((0..alternatives.len()).map(|alternative| Item {
+                                rule: Some(*next),
+                                alternative,
+                                dot: 0,
+                                origin: position,
+                            }));
+                        }
+                        _ => {}
+                    },
+                    // Complete every item that was waiting for this rule
+                    None => {
+                        found.extend(
+                            sets[item.origin]
+                                .iter()
+                                .filter(|parent| {
+                                    self.sequence(&start, parent).get(parent.dot).copied()
+                                        == item.rule
+                                })
+                                .map(|parent| parent.advance()),
+                        );
+                    }
+                }
+
+                for new_item in found {
+                    if seen[position].insert(new_item) {
+                        sets[position].push(new_item);
+                    }
+                }
+                if let Some(new_item) = scanned {
+                    if seen[position + 1].insert(new_item) {
+                        sets[position + 1].push(new_item);
+                    }
+                }
+            }
+        }
+
+        seen[chars.len()].contains(&goal.advance())
+    }
+}
+
+#[aoc_generator(day19)]
+fn parse_input_day19(input: &str) -> Result<(Grammar, Vec<String>), ParseError> {
+    let source = Source::new(19, input);
+    let mut sections = input.splitn(2, "\n\n");
+    let rules = sections.next().unwrap();
+    let mut grammar = Grammar::default();
+    for line in rules.lines() {
+        let mut parts = line.splitn(2, ": ");
+        let id = source.parse(parts.next().unwrap(), "a rule number")?;
+        let body = parts
+            .next()
+            .ok_or_else(|| source.error(line, "a `number: rule` line"))?;
+        grammar.insert(id, Rule::parse(&source, body)?);
     }
+
+    // Every rule a sequence refers to has to be defined, rule 0 included
+    if !grammar.rules.contains_key(&0) {
+        return Err(source.error(rules, "a definition of rule 0"));
+    }
+    for body in rules
+        .lines()
+        .filter_map(|line| line.split_once(": ").map(|(_, body)| body))
+    {
+        if body.starts_with('"') {
+            continue;
+        }
+        for reference in body.split('|').flat_map(str::split_whitespace) {
+            let id = source.parse(reference, "a rule number")?;
+            if !grammar.rules.contains_key(&id) {
+                return Err(source.error(reference, "a defined rule number"));
+            }
+        }
+    }
+
+    let messages = sections
+        .next()
+        .ok_or_else(|| source.end("the messages"))?
+        .lines()
+        .map(|line| line.to_owned())
+        .collect::<Vec<_>>();
+
+    Ok((grammar, messages))
 }
 
 #[aoc(day19, part1)]
-fn day19_part1(input: &str) -> Option<usize> {
-    let (rules, messages) = parse_input_day19(input);
-    let regex_0 = calculate_regex_for_rule_p1(&rules, &0);
-    let rule_0_reg = Regex::new(&format!("^{}$", regex_0)).unwrap();
+fn day19_part1((grammar, messages): &(Grammar, Vec<String>)) -> Option<usize> {
     messages
         .iter()
-        .filter(|message| rule_0_reg.is_match(message))
+        .filter(|message| grammar.matches(0, message))
         .count()
         .into()
 }
 
 #[aoc(day19, part2)]
-fn day19_part2(input: &str) -> Option<usize> {
-    let (mut rules, messages) = parse_input_day19(input);
-    rules[8] = "42 | 42 8".to_string();
-    rules[11] = "42 31 | 42 11 31".to_string();
+fn day19_part2((grammar, messages): &(Grammar, Vec<String>)) -> Option<usize> {
+    if !grammar.rules.contains_key(&42) || !grammar.rules.contains_key(&31) {
+        return None;
+    }
+    let mut grammar = grammar.clone();
+    grammar.insert(8, Rule::Alternatives(vec![vec![42], vec![42, 8]]));
+    grammar.insert(11, Rule::Alternatives(vec![vec![42, 31], vec![42, 11, 31]]));
 
-    let regex_0 = calculate_regex_for_rule_p2(&rules, &0);
-    let rule_0_reg = Regex::new(&format!("^{}$", regex_0)).unwrap();
     messages
         .iter()
-        .filter(|message| rule_0_reg.is_match(message))
+        .filter(|message| grammar.matches(0, message))
         .count()
         .into()
 }
@@ -134,29 +224,26 @@ mod tests {
     #[test]
     fn test_part1() {
         let input = "0: 4 1 5\n1: 2 3 | 3 2\n2: 4 4 | 5 5\n3: 4 5 | 5 4\n4: \"a\"\n5: \"b\"\n\nababbb\nbababa\nabbbab\naaabbb\naaaabbb";
-        assert_eq!(day19_part1(input), Some(2));
+        assert_eq!(day19_part1(&parse_input_day19(input).unwrap()), Some(2));
+
+        // Left recursion: 0 = 0 1 | 1 == (1)+
+        let input = "0: 0 1 | 1\n1: \"a\"\n\na\naaaa\naab";
+        assert_eq!(day19_part1(&parse_input_day19(input).unwrap()), Some(2));
+
+        let error = parse_input_day19("0: 1 2\n1: \"a\"\n\na").unwrap_err();
+        assert_eq!((error.line, error.column, error.text.as_str()), (1, 6, "2"));
+        let error = parse_input_day19("1: \"a\"\n\na").unwrap_err();
+        assert_eq!(error.expected, "a definition of rule 0");
+
+        // Ids as large as they come are rules like any other
+        let input = "0: 18446744073709551615\n18446744073709551615: \"a\"\n\na\nb";
+        assert_eq!(day19_part1(&parse_input_day19(input).unwrap()), Some(1));
     }
 
     #[test]
     fn test_part2() {
         let input = "42: 9 14 | 10 1\n9: 14 27 | 1 26\n10: 23 14 | 28 1\n1: \"a\"\n11: 42 31\n5: 1 14 | 15 1\n19: 14 1 | 14 14\n12: 24 14 | 19 1\n16: 15 1 | 14 14\n31: 14 17 | 1 13\n6: 14 14 | 1 14\n2: 1 24 | 14 4\n0: 8 11\n13: 14 3 | 1 12\n15: 1 | 14\n17: 14 2 | 1 7\n23: 25 1 | 22 14\n28: 16 1\n4: 1 1\n20: 14 14 | 1 15\n3: 5 14 | 16 1\n27: 1 6 | 14 18\n14: \"b\"\n21: 14 1 | 1 14\n25: 1 1 | 1 14\n22: 14 14\n8: 42\n26: 14 22 | 1 20\n18: 15 15\n7: 14 5 | 1 21\n24: 14 1\n\nabbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa\nbbabbbbaabaabba\nbabbbbaabbbbbabbbbbbaabaaabaaa\naaabbbbbbaaaabaababaabababbabaaabbababababaaa\nbbbbbbbaaaabbbbaaabbabaaa\nbbbababbbbaaaaaaaabbababaaababaabab\nababaaaaaabaaab\nababaaaaabbbaba\nbaabbaaaabbaaaababbaababb\nabbbbabbbbaaaababbbbbbaaaababb\naaaaabbaabaaaaababaa\naaaabbaaaabbaaa\naaaabbaabbaaaaaaabbbabbbaaabbaabaaa\nbabaaabbbaaabaababbaabababaaab\naabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba";
-        assert_eq!(day19_part2(input), Some(12));
+        assert_eq!(day19_part1(&parse_input_day19(input).unwrap()), Some(3));
+        assert_eq!(day19_part2(&parse_input_day19(input).unwrap()), Some(12));
     }
 }
-
-/*
-0: 4 1 5
-1: 2 3 | 3 2
-2: 4 4 | 5 5
-3: 4 5 | 5 4
-4: "a"
-5: "b"
-
-
-5: ^(b)$
-4: ^(a)$
-3: ^((ab)|(ba))$
-2: ^((aa)|(bb))$
-1: ^((((aa)|(bb))((ab)|(ba)))|(((ab)|(ba))((aa)|(bb))))$
-0: ^((a)((((aa)|(bb))((ab)|(ba)))|(((ab)|(ba))((aa)|(bb))))(b))$
-*/
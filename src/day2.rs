@@ -2,23 +2,41 @@ use aoc_runner_derive::{aoc, aoc_generator};
 
 use regex::Regex;
 
+use crate::parse::{ParseError, Source};
+
 #[aoc_generator(day2)]
-fn parse_input_day2(input: &str) -> Result<Vec<(u32, u32, char, String)>, String> {
+fn parse_input_day2(input: &str) -> Result<Vec<(u32, u32, char, String)>, ParseError> {
+    let source = Source::new(2, input);
     let regex =
-        Regex::new(r"(?P<minimum>\d+)-(?P<maximum>\d+) (?P<character>\w): (?P<password>\w+)")
+        Regex::new(r"^(?P<minimum>\d+)-(?P<maximum>\d+) (?P<character>\w): (?P<password>\w+)$")
             .unwrap();
 
-    Ok(regex
-        .captures_iter(input)
-        .map(|cap| {
-            (
-                cap[1].parse().unwrap(),
-                cap[2].parse().unwrap(),
-                cap[3].parse().unwrap(),
-                String::from(&cap[4]),
-            )
+    input
+        .lines()
+        .map(|line| {
+            let cap = regex
+                .captures(line)
+                .ok_or_else(|| source.error(line, "a `min-max letter: password` policy"))?;
+            // Both numbers are positions in the password for part 2
+            let password = &cap[4];
+            let position = |index: usize, expected| {
+                let text = cap.get(index).unwrap().as_str();
+                match source.parse::<u32>(text, expected)? {
+                    0 => Err(source.error(text, "a position of at least 1")),
+                    value if value as usize > password.len() => {
+                        Err(source.error(text, "a position within the password"))
+                    }
+                    value => Ok(value),
+                }
+            };
+            Ok((
+                position(1, "a minimum count")?,
+                position(2, "a maximum count")?,
+                source.parse(cap.get(3).unwrap().as_str(), "a letter")?,
+                String::from(password),
+            ))
         })
-        .collect::<Vec<(u32, u32, char, String)>>())
+        .collect()
 }
 
 #[aoc(day2, part1)]
@@ -66,5 +84,13 @@ mod tests {
     fn test_part2() {
         let input = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc";
         assert_eq!(day2_part2(&parse_input_day2(input).unwrap()), Some(1));
+
+        let error = parse_input_day2("0-3 a: abc").unwrap_err();
+        assert_eq!(
+            (error.column, error.expected),
+            (1, "a position of at least 1")
+        );
+        let error = parse_input_day2("1-4 a: abc").unwrap_err();
+        assert_eq!((error.column, error.text.as_str()), (3, "4"));
     }
 }
@@ -1,6 +1,22 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    grid::Grid,
+    parse::{ParseError, Source},
+};
+
+type Pixels = Grid<char>;
+
+const SEA_MONSTER: [&str; 3] = [
+    "                  # ",
+    "#    ##    ##    ###",
+    " #  #  #  #  #  #   ",
+];
 
 #[derive(Debug, PartialEq)]
 enum Edge {
@@ -38,50 +54,192 @@ impl From<usize> for Edge {
 }
 
 #[derive(Debug)]
-struct Tile {
+pub struct Tile {
     id: usize,
     edges: Vec<String>,
+    pixels: Pixels,
 }
 
 impl Tile {
-    fn new(id: usize, edges: Vec<String>) -> Self {
-        Self { id, edges }
+    fn new(id: usize, edges: Vec<String>, pixels: Pixels) -> Self {
+        Self { id, edges, pixels }
     }
 }
 
+fn top(pixels: &Pixels) -> String {
+    pixels.row(0).iter().collect()
+}
+
+fn bottom(pixels: &Pixels) -> String {
+    pixels.row(pixels.height() - 1).iter().collect()
+}
+
+fn left(pixels: &Pixels) -> String {
+    pixels.column(0).collect()
+}
+
+fn right(pixels: &Pixels) -> String {
+    pixels.column(pixels.width() - 1).collect()
+}
+
+// An edge and its reverse are the same edge seen from the two matching tiles.
+fn canonical(edge: &str) -> String {
+    let reverse: String = edge.chars().rev().collect();
+    if reverse.as_str() < edge {
+        reverse
+    } else {
+        edge.to_string()
+    }
+}
+
+/// The picture left after placing every tile and stripping their borders.
+#[derive(Debug)]
+pub struct Image {
+    pixels: Pixels,
+}
+
+impl Image {
+    pub fn pixels(&self) -> &Pixels {
+        &self.pixels
+    }
+
+    fn water_roughness(&self) -> Option<usize> {
+        let monster: Vec<(usize, usize)> = SEA_MONSTER
+            .iter()
+            .enumerate()
+            .flat_map(|(row, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '#')
+                    .map(move |(column, _)| (row, column))
+            })
+            .collect();
+        let height = SEA_MONSTER.len();
+        let width = SEA_MONSTER[0].len();
+        let size = self.pixels.width();
+        if size < height || size < width {
+            return None;
+        }
+
+        let rough = self.pixels.iter().filter(|&&c| c == '#').count();
+
+        self.pixels.orientations().into_iter().find_map(|pixels| {
+            let mut marked = HashSet::new();
+            for row in 0..=size - height {
+                for column in 0..=size - width {
+                    if monster
+                        .iter()
+                        .all(|&(dr, dc)| pixels[(row + dr, column + dc)] == '#')
+                    {
+                        marked.extend(monster.iter().map(|&(dr, dc)| (row + dr, column + dc)));
+                    }
+                }
+            }
+
+            if marked.is_empty() {
+                None
+            } else {
+                Some(rough - marked.len())
+            }
+        })
+    }
+}
+
+impl fmt::Display for Image {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pixels)
+    }
+}
+
+/// Places every tile into a square, starting from a corner and matching each
+/// new tile against its left and top neighbours, then strips the borders.
+pub fn assemble(tiles: &[Tile]) -> Option<Image> {
+    let size = (tiles.len() as f64).sqrt() as usize;
+    if size * size != tiles.len() {
+        return None;
+    }
+
+    let mut edge_counts = HashMap::<String, usize>::new();
+    for tile in tiles.iter() {
+        for edge in tile.edges.iter() {
+            *edge_counts.entry(canonical(edge)).or_default() += 1;
+        }
+    }
+    let is_outer = |edge: &str| edge_counts.get(&canonical(edge)) == Some(&1);
+
+    let corner = tiles
+        .iter()
+        .find(|tile| tile.edges.iter().filter(|edge| is_outer(edge)).count() == 2)?;
+    let start = corner
+        .pixels
+        .orientations()
+        .into_iter()
+        .find(|pixels| is_outer(&top(pixels)) && is_outer(&left(pixels)))?;
+
+    let mut used = HashSet::new();
+    used.insert(corner.id);
+    let mut placed = Vec::with_capacity(tiles.len());
+    placed.push(start);
+
+    for position in 1..tiles.len() {
+        let (row, column) = (position / size, position % size);
+        let (id, pixels) = tiles
+            .iter()
+            .filter(|tile| !used.contains(&tile.id))
+            .find_map(|tile| {
+                tile.pixels
+                    .orientations()
+                    .into_iter()
+                    .find(|candidate| {
+                        (column == 0 || left(candidate) == right(&placed[position - 1]))
+                            && (row == 0 || top(candidate) == bottom(&placed[position - size]))
+                    })
+                    .map(|pixels| (tile.id, pixels))
+            })?;
+        used.insert(id);
+        placed.push(pixels);
+    }
+
+    let inner = placed[0].width() - 2;
+    let pixels = Grid::from_fn(size * inner, size * inner, |(row, column)| {
+        let tile = &placed[(row / inner) * size + column / inner];
+        tile[(row % inner + 1, column % inner + 1)]
+    });
+
+    Some(Image { pixels })
+}
+
 #[aoc_generator(day20)]
-fn parse_input_day20(input: &str) -> Vec<Tile> {
+pub fn parse_input_day20(input: &str) -> Result<Vec<Tile>, ParseError> {
+    let source = Source::new(20, input);
     input
         .split("\n\n")
         .map(|tile| {
-            let lines: Vec<_> = tile.lines().collect();
-            let id = lines[0][5..lines[0].len() - 1].parse::<usize>().unwrap();
-
-            let mut edges = Vec::with_capacity(4);
-            // Top Edge
-            edges.push(lines[1].to_string());
-
-            // Right Edge
-            edges.push(
-                lines[1..]
-                    .iter()
-                    .map(|&line| line.chars().last().unwrap())
-                    .collect(),
-            );
-
-            // Bottom Edge
-            edges.push(lines[lines.len() - 1].chars().rev().collect());
-
-            // Left Edge
-            edges.push(
-                lines[1..]
-                    .iter()
-                    .map(|&line| line.chars().next().unwrap())
-                    .rev()
-                    .collect(),
-            );
-
-            Tile::new(id, edges)
+            let (header, body) = tile
+                .split_once('\n')
+                .ok_or_else(|| source.error(tile, "a tile header and at least 2x2 pixels"))?;
+            let id = header
+                .strip_prefix("Tile ")
+                .and_then(|header| header.strip_suffix(':'))
+                .ok_or_else(|| source.error(header, "a `Tile <id>:` header"))?;
+            let id = source.parse(id, "a tile id")?;
+
+            let pixels = Grid::parse(&source, body, "`#` or `.`", |c| {
+                matches!(c, '#' | '.').then_some(c)
+            })?;
+            if pixels.width() != pixels.height() || pixels.width() < 2 {
+                return Err(source.error(body, "a square tile of at least 2x2 pixels"));
+            }
+
+            // Edges are read clockwise: Top, Right, Bottom, Left
+            let edges = vec![
+                top(&pixels),
+                right(&pixels),
+                bottom(&pixels).chars().rev().collect(),
+                left(&pixels).chars().rev().collect(),
+            ];
+
+            Ok(Tile::new(id, edges, pixels))
         })
         .collect()
 }
@@ -133,10 +291,15 @@ fn day20_part1(tiles: &[Tile]) -> Option<usize> {
                 None
             }
         })
-        .fold(1, |acc, id| id * acc)
+        .product::<usize>()
         .into()
 }
 
+#[aoc(day20, part2)]
+fn day20_part2(tiles: &[Tile]) -> Option<usize> {
+    assemble(tiles)?.water_roughness()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -144,6 +307,18 @@ mod tests {
     #[test]
     fn test_part1() {
         let input = "Tile 2311:\n..##.#..#.\n##..#.....\n#...##..#.\n####.#...#\n##.##.###.\n##...#.###\n.#.#.#..##\n..#....#..\n###...#.#.\n..###..###\n\nTile 1951:\n#.##...##.\n#.####...#\n.....#..##\n#...######\n.##.#....#\n.###.#####\n###.##.##.\n.###....#.\n..#.#..#.#\n#...##.#..\n\nTile 1171:\n####...##.\n#..##.#..#\n##.#..#.#.\n.###.####.\n..###.####\n.##....##.\n.#...####.\n#.##.####.\n####..#...\n.....##...\n\nTile 1427:\n###.##.#..\n.#..#.##..\n.#.##.#..#\n#.#.#.##.#\n....#...##\n...##..##.\n...#.#####\n.#.####.#.\n..#..###.#\n..##.#..#.\n\nTile 1489:\n##.#.#....\n..##...#..\n.##..##...\n..#...#...\n#####...#.\n#..#.#.#.#\n...#.#.#..\n##.#...##.\n..##.##.##\n###.##.#..\n\nTile 2473:\n#....####.\n#..#.##...\n#.##..#...\n######.#.#\n.#...#.#.#\n.#########\n.###.#..#.\n########.#\n##...##.#.\n..###.#.#.\n\nTile 2971:\n..#.#....#\n#...###...\n#.#.###...\n##.##..#..\n.#####..##\n.#..####.#\n#..#.#..#.\n..####.###\n..#.#.###.\n...#.#.#.#\n\nTile 2729:\n...#.#.#.#\n####.#....\n..#.#.....\n....#..#.#\n.##..##.#.\n.#.####...\n####.#.#..\n##.####...\n##..#.##..\n#.##...##.\n\nTile 3079:\n#.#.#####.\n.#..######\n..#.......\n######....\n####.#..#.\n.#...#.##.\n#.#####.##\n..#.###...\n..#.......\n..#.###...";
-        assert_eq!(day20_part1(&parse_input_day20(input)), Some(20899048083289));
+        assert_eq!(
+            day20_part1(&parse_input_day20(input).unwrap()),
+            Some(20899048083289)
+        );
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = "Tile 2311:\n..##.#..#.\n##..#.....\n#...##..#.\n####.#...#\n##.##.###.\n##...#.###\n.#.#.#..##\n..#....#..\n###...#.#.\n..###..###\n\nTile 1951:\n#.##...##.\n#.####...#\n.....#..##\n#...######\n.##.#....#\n.###.#####\n###.##.##.\n.###....#.\n..#.#..#.#\n#...##.#..\n\nTile 1171:\n####...##.\n#..##.#..#\n##.#..#.#.\n.###.####.\n..###.####\n.##....##.\n.#...####.\n#.##.####.\n####..#...\n.....##...\n\nTile 1427:\n###.##.#..\n.#..#.##..\n.#.##.#..#\n#.#.#.##.#\n....#...##\n...##..##.\n...#.#####\n.#.####.#.\n..#..###.#\n..##.#..#.\n\nTile 1489:\n##.#.#....\n..##...#..\n.##..##...\n..#...#...\n#####...#.\n#..#.#.#.#\n...#.#.#..\n##.#...##.\n..##.##.##\n###.##.#..\n\nTile 2473:\n#....####.\n#..#.##...\n#.##..#...\n######.#.#\n.#...#.#.#\n.#########\n.###.#..#.\n########.#\n##...##.#.\n..###.#.#.\n\nTile 2971:\n..#.#....#\n#...###...\n#.#.###...\n##.##..#..\n.#####..##\n.#..####.#\n#..#.#..#.\n..####.###\n..#.#.###.\n...#.#.#.#\n\nTile 2729:\n...#.#.#.#\n####.#....\n..#.#.....\n....#..#.#\n.##..##.#.\n.#.####...\n####.#.#..\n##.####...\n##..#.##..\n#.##...##.\n\nTile 3079:\n#.#.#####.\n.#..######\n..#.......\n######....\n####.#..#.\n.#...#.##.\n#.#####.##\n..#.###...\n..#.......\n..#.###...";
+        assert_eq!(day20_part2(&parse_input_day20(input).unwrap()), Some(273));
+
+        let image = assemble(&parse_input_day20(input).unwrap()).unwrap();
+        assert_eq!((image.pixels().width(), image.pixels().height()), (24, 24));
     }
 }
@@ -1,17 +1,26 @@
-use aoc_runner_derive::aoc;
+use aoc_runner_derive::{aoc, aoc_generator};
 
 use std::collections::{HashMap, HashSet};
 
-fn parse_input_day21(input: &str) -> (HashMap<&str, usize>, HashMap<&str, &str>) {
+use crate::parse::{ParseError, Source};
+
+type Foods = (HashMap<String, usize>, HashMap<String, String>);
+
+#[aoc_generator(day21)]
+fn parse_input_day21(input: &str) -> Result<Foods, ParseError> {
+    let source = Source::new(21, input);
     // Allergen => [Ingredients]
     let mut one_to_many = HashMap::new();
     let mut occurrences = HashMap::new();
-    input.lines().for_each(|line| {
-        let mut split = line.split(" (contains ");
+    for line in input.lines() {
+        let mut split = line.splitn(2, " (contains ");
         let ingredients = split.next().unwrap().split(' ').collect::<HashSet<_>>();
 
-        let allergens = split.next().unwrap();
-        let allergens: Vec<_> = (&allergens[..allergens.len() - 1]).split(", ").collect();
+        let allergens = split
+            .next()
+            .and_then(|allergens| allergens.strip_suffix(')'))
+            .ok_or_else(|| source.error(line, "`<ingredients> (contains <allergens>)`"))?;
+        let allergens: Vec<_> = allergens.split(", ").collect();
 
         for &allergen in allergens.iter() {
             let set = one_to_many
@@ -21,46 +30,39 @@ fn parse_input_day21(input: &str) -> (HashMap<&str, usize>, HashMap<&str, &str>)
         }
 
         for ingredient in ingredients.iter() {
-            *occurrences.entry(*ingredient).or_default() += 1;
+            *occurrences.entry(ingredient.to_string()).or_default() += 1;
         }
-    });
+    }
 
     // Allergen => Ingredient
     let mut one_to_one = HashMap::new();
     while let Some((&allergen, ingredients)) = one_to_many.iter().find(|(_, set)| set.len() == 1) {
         let ing = *ingredients.iter().next().unwrap();
-        one_to_one.insert(allergen, ing);
+        one_to_one.insert(allergen.to_string(), ing.to_string());
         one_to_many.iter_mut().for_each(|(_, set)| {
             set.remove(ing);
         });
     }
 
-    (occurrences, one_to_one)
+    Ok((occurrences, one_to_one))
 }
 
 #[aoc(day21, part1)]
-fn day21_part1(input: &str) -> usize {
-    let (occurrences, one_to_one) = parse_input_day21(input);
+fn day21_part1((occurrences, one_to_one): &Foods) -> usize {
     occurrences
         .iter()
-        .filter(|(&allergen, _)| {
-            one_to_one
-                .values()
-                .find(|&&some| some == allergen)
-                .is_none()
-        })
-        .map(|(&_, count)| count)
+        .filter(|(allergen, _)| !one_to_one.values().any(|some| some == *allergen))
+        .map(|(_, count)| count)
         .sum()
 }
 
 #[aoc(day21, part2)]
-fn day21_part2(input: &str) -> String {
-    let (_, one_to_one) = parse_input_day21(input);
-    let mut res = one_to_one.keys().copied().collect::<Vec<_>>();
+fn day21_part2((_, one_to_one): &Foods) -> String {
+    let mut res = one_to_one.keys().collect::<Vec<_>>();
     res.sort_unstable();
 
     res.iter()
-        .map(|&key| *one_to_one.get(key).unwrap())
+        .map(|&key| one_to_one[key].as_str())
         .collect::<Vec<_>>()
         .join(",")
 }
@@ -72,12 +74,15 @@ mod tests {
     #[test]
     fn test_part2() {
         let input = "mxmxvkd kfcds sqjhc nhms (contains dairy, fish)\ntrh fvjkl sbzzf mxmxvkd (contains dairy)\nsqjhc fvjkl (contains soy)\nsqjhc mxmxvkd sbzzf (contains fish)";
-        assert_eq!(day21_part1(input), 5);
+        assert_eq!(day21_part1(&parse_input_day21(input).unwrap()), 5);
     }
 
     #[test]
     fn test_part1() {
         let input = "mxmxvkd kfcds sqjhc nhms (contains dairy, fish)\ntrh fvjkl sbzzf mxmxvkd (contains dairy)\nsqjhc fvjkl (contains soy)\nsqjhc mxmxvkd sbzzf (contains fish)";
-        assert_eq!(day21_part2(input), "mxmxvkd,sqjhc,fvjkl");
+        assert_eq!(
+            day21_part2(&parse_input_day21(input).unwrap()),
+            "mxmxvkd,sqjhc,fvjkl"
+        );
     }
 }
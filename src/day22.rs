@@ -1,29 +1,34 @@
 use std::collections::{HashSet, VecDeque};
 
-use aoc_runner_derive::aoc;
+use aoc_runner_derive::{aoc, aoc_generator};
+
+use crate::parse::{ParseError, Source};
 
 enum Winner {
     PlayerOne,
     PlayerTwo,
 }
 
-fn parse_input_day22(input: &str) -> Vec<VecDeque<usize>> {
-    input
-        .split("\n\n")
-        .map(|deck| {
-            deck.lines()
-                .skip(1)
-                .map(|card| card.parse::<usize>().unwrap())
-                .collect()
-        })
-        .collect()
+#[aoc_generator(day22)]
+fn parse_input_day22(input: &str) -> Result<(VecDeque<usize>, VecDeque<usize>), ParseError> {
+    let source = Source::new(22, input);
+    let mut decks = input.splitn(2, "\n\n").map(|deck| {
+        deck.lines()
+            .skip(1)
+            .map(|card| source.parse(card, "a card number"))
+            .collect::<Result<VecDeque<_>, _>>()
+    });
+
+    let player_1 = decks.next().unwrap()?;
+    let player_2 = decks
+        .next()
+        .ok_or_else(|| source.end("a second player's deck"))??;
+    Ok((player_1, player_2))
 }
 
 #[aoc(day22, part1)]
-fn day22_part1(input: &str) -> Option<usize> {
-    let mut players = parse_input_day22(input);
-    let mut player_2 = players.pop().unwrap();
-    let mut player_1 = players.pop().unwrap();
+fn day22_part1(players: &(VecDeque<usize>, VecDeque<usize>)) -> Option<usize> {
+    let (mut player_1, mut player_2) = players.clone();
 
     while !player_1.is_empty() && !player_2.is_empty() {
         let p1_card = player_1.pop_front().unwrap();
@@ -53,10 +58,8 @@ fn day22_part1(input: &str) -> Option<usize> {
 }
 
 #[aoc(day22, part2)]
-fn day22_part2(input: &str) -> Option<usize> {
-    let mut players = parse_input_day22(input);
-    let mut player_2 = players.pop().unwrap();
-    let mut player_1 = players.pop().unwrap();
+fn day22_part2(players: &(VecDeque<usize>, VecDeque<usize>)) -> Option<usize> {
+    let (mut player_1, mut player_2) = players.clone();
 
     let winner = match sub_game(&mut player_1, &mut player_2) {
         Winner::PlayerOne => player_1,
@@ -122,12 +125,12 @@ mod tests {
     #[test]
     fn test_part1() {
         let input = "Player 1:\n9\n2\n6\n3\n1\n\nPlayer 2:\n5\n8\n4\n7\n10";
-        assert_eq!(day22_part1(input), Some(306));
+        assert_eq!(day22_part1(&parse_input_day22(input).unwrap()), Some(306));
     }
 
     #[test]
     fn test_part2() {
         let input = "Player 1:\n9\n2\n6\n3\n1\n\nPlayer 2:\n5\n8\n4\n7\n10";
-        assert_eq!(day22_part2(input), Some(291));
+        assert_eq!(day22_part2(&parse_input_day22(input).unwrap()), Some(291));
     }
 }
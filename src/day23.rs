@@ -0,0 +1,141 @@
+use aoc_runner_derive::{aoc, aoc_generator};
+
+use crate::parse::{ParseError, Source};
+
+#[aoc_generator(day23)]
+fn parse_input_day23(input: &str) -> Result<Vec<usize>, ParseError> {
+    let source = Source::new(23, input);
+    let mut seen = vec![false; input.len() + 1];
+    let labels = input
+        .char_indices()
+        .map(|(index, cup)| {
+            let label = cup.to_digit(10).map(|label| label as usize);
+            match label {
+                Some(label) if label >= 1 && label < seen.len() && !seen[label] => {
+                    seen[label] = true;
+                    Ok(label)
+                }
+                _ => Err(source.error(
+                    &input[index..index + cup.len_utf8()],
+                    "every cup label from 1 up to the number of cups, once",
+                )),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Every move picks up three cups and keeps one to go after them
+    if labels.len() < 4 {
+        return Err(source.end("at least 4 cups"));
+    }
+    Ok(labels)
+}
+
+// The circle is stored as a linked list inside an array,
+// `next[label]` is the label of the cup clockwise of `label`.
+// Labels are kept as `u32` so the million cups stay cache friendly.
+struct Cups {
+    next: Vec<u32>,
+    current: u32,
+}
+
+impl Cups {
+    // Cups after the given labels continue counting up to `total`.
+    fn new(labels: &[usize], total: usize) -> Self {
+        let mut order = labels.to_vec();
+        order.extend(labels.len() + 1..=total);
+
+        let mut next = vec![0; total + 1];
+        for (&cup, &following) in order.iter().zip(order.iter().cycle().skip(1)) {
+            next[cup] = following as u32;
+        }
+
+        Self {
+            next,
+            current: order[0] as u32,
+        }
+    }
+
+    fn play(&mut self, moves: usize) {
+        for _ in 0..moves {
+            self.step();
+        }
+    }
+
+    fn step(&mut self) {
+        let max = (self.next.len() - 1) as u32;
+        let first = self.next[self.current as usize];
+        let second = self.next[first as usize];
+        let third = self.next[second as usize];
+        self.next[self.current as usize] = self.next[third as usize];
+
+        let mut destination = self.current;
+        loop {
+            destination = if destination == 1 {
+                max
+            } else {
+                destination - 1
+            };
+            if destination != first && destination != second && destination != third {
+                break;
+            }
+        }
+
+        self.next[third as usize] = self.next[destination as usize];
+        self.next[destination as usize] = first;
+        self.current = self.next[self.current as usize];
+    }
+
+    fn after(&self, label: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut cup = label;
+        std::iter::from_fn(move || {
+            cup = self.next[cup] as usize;
+            Some(cup)
+        })
+        .take_while(move |&cup| cup != label)
+    }
+}
+
+#[aoc(day23, part1)]
+fn day23_part1(labels: &[usize]) -> String {
+    let mut cups = Cups::new(labels, labels.len());
+    cups.play(100);
+    cups.after(1).map(|cup| cup.to_string()).collect()
+}
+
+#[aoc(day23, part2)]
+fn day23_part2(labels: &[usize]) -> usize {
+    let mut cups = Cups::new(labels, 1_000_000);
+    cups.play(10_000_000);
+    cups.after(1).take(2).product()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part1() {
+        let input = "389125467";
+        let mut cups = Cups::new(&parse_input_day23(input).unwrap(), input.len());
+        cups.play(10);
+        assert_eq!(
+            cups.after(1).map(|cup| cup.to_string()).collect::<String>(),
+            "92658374"
+        );
+
+        assert_eq!(day23_part1(&parse_input_day23(input).unwrap()), "67384529");
+        assert_eq!(
+            parse_input_day23("").unwrap_err().expected,
+            "at least 4 cups"
+        );
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = "389125467";
+        assert_eq!(
+            day23_part2(&parse_input_day23(input).unwrap()),
+            149245887792
+        );
+    }
+}
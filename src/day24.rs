@@ -0,0 +1,62 @@
+use std::collections::HashSet;
+
+use aoc_runner_derive::{aoc, aoc_generator};
+
+use crate::hex::{Direction, Hex, Tiling};
+use crate::life::{Life, Rule};
+use crate::parse::{ParseError, Source};
+
+#[aoc_generator(day24)]
+fn parse_input_day24(input: &str) -> Result<Vec<Vec<Direction>>, ParseError> {
+    let source = Source::new(24, input);
+    if input.trim().is_empty() {
+        return Err(source.end("at least one path of tile directions"));
+    }
+    input
+        .lines()
+        .map(|line| Direction::parse_path(&source, line))
+        .collect()
+}
+
+/// Flips the tile at the end of each path, returns the black ones.
+fn lay_out(paths: &[Vec<Direction>]) -> HashSet<Hex> {
+    let mut black = HashSet::new();
+    for path in paths.iter() {
+        let hex = Hex::origin().walk(path);
+        if !black.remove(&hex) {
+            black.insert(hex);
+        }
+    }
+    black
+}
+
+#[aoc(day24, part1)]
+fn day24_part1(paths: &[Vec<Direction>]) -> Option<usize> {
+    Some(lay_out(paths).len())
+}
+
+#[aoc(day24, part2)]
+fn day24_part2(paths: &[Vec<Direction>]) -> Option<usize> {
+    // Black tiles are the live cells
+    let mut floor = Life::new(Tiling, Rule::new(&[2], &[1, 2]), lay_out(paths));
+    floor.run(100);
+    Some(floor.population())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part1() {
+        let input = "sesenwnenenewseeswwswswwnenewsewsw\nneeenesenwnwwswnenewnwwsewnenwseswesw\nseswneswswsenwwnwse\nnwnwneseeswswnenewneswwnewseswneseene\nswweswneswnenwsewnwneneseenw\neesenwseswswnenwswnwnwsewwnwsene\nsewnenenenesenwsewnenwwwse\nwenwwweseeeweswwwnwwe\nwsweesenenewnwwnwsenewsenwwsesesenwne\nneeswseenwwswnwswswnw\nnenwswwsewswnenenewsenwsenwnesesenew\nenewnwewneswsewnwswenweswnenwsenwsw\nsweneswneswneneenwnewenewwneswswnese\nswwesenesewenwneswnwwneseswwne\nenesenwswwswneneswsenwnewswseenwsese\nwnwnesenesenenwwnenwsewesewsesesew\nnenewswnwewswnenesenwnesewesw\neneswnwswnwsenenwnwnwwseeswneewsenese\nneswnwewnwnwseenwseesewsenwsweewe\nwseweeenwnesenwwwswnew";
+        assert_eq!(day24_part1(&parse_input_day24(input).unwrap()), Some(10));
+        assert!(parse_input_day24("").is_err());
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = "sesenwnenenewseeswwswswwnenewsewsw\nneeenesenwnwwswnenewnwwsewnenwseswesw\nseswneswswsenwwnwse\nnwnwneseeswswnenewneswwnewseswneseene\nswweswneswnenwsewnwneneseenw\neesenwseswswnenwswnwnwsewwnwsene\nsewnenenenesenwsewnenwwwse\nwenwwweseeeweswwwnwwe\nwsweesenenewnwwnwsenewsenwwsesesenwne\nneeswseenwwswnwswswnw\nnenwswwsewswnenenewsenwsenwnesesenew\nenewnwewneswsewnwswenweswnenwsenwsw\nsweneswneswneneenwnewenewwneswswnese\nswwesenesewenwneswnwwneseswwne\nenesenwswwswneneswsenwnewswseenwsese\nwnwnesenesenenwwnenwsewesewsesesew\nnenewswnwewswnenesenwnesewesw\neneswnwswnwsenenwnwnwwseeswneewsenese\nneswnwewnwnwseenwseesewsenwsweewe\nwseweeenwnesenwwwswnew";
+        assert_eq!(day24_part2(&parse_input_day24(input).unwrap()), Some(2208));
+    }
+}
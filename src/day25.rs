@@ -0,0 +1,51 @@
+use aoc_runner_derive::{aoc, aoc_generator};
+
+use crate::modular::{discrete_log, pow_mod};
+use crate::parse::{ParseError, Source};
+
+const SUBJECT: u64 = 7;
+const MODULUS: u64 = 20201227;
+
+#[aoc_generator(day25)]
+fn parse_input_day25(input: &str) -> Result<(u64, u64), ParseError> {
+    let source = Source::new(25, input);
+    let mut keys = input.lines();
+    let mut key = |expected| {
+        let key = keys.next().ok_or_else(|| source.end(expected))?;
+        match source.parse(key, expected)? {
+            key if key < MODULUS => Ok(key),
+            _ => Err(source.error(key, "a key below 20201227")),
+        }
+    };
+    Ok((key("the card's public key")?, key("the door's public key")?))
+}
+
+fn loop_size(public_key: u64) -> Option<u64> {
+    discrete_log(SUBJECT, public_key, MODULUS)
+}
+
+#[aoc(day25, part1)]
+fn day25_part1(&(card_key, door_key): &(u64, u64)) -> Option<u64> {
+    let card_loop_size = loop_size(card_key)?;
+    Some(pow_mod(door_key, card_loop_size, MODULUS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loop_size() {
+        assert_eq!(loop_size(5764801), Some(8));
+        assert_eq!(loop_size(17807724), Some(11));
+    }
+
+    #[test]
+    fn test_part1() {
+        let input = "5764801\n17807724";
+        assert_eq!(
+            day25_part1(&parse_input_day25(input).unwrap()),
+            Some(14897079)
+        );
+    }
+}
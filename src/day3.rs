@@ -1,11 +1,16 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 
+use crate::{
+    grid::Grid,
+    parse::{ParseError, Source},
+};
+
 #[aoc_generator(day3)]
-fn parse_input_day3(input: &str) -> Vec<Vec<u8>> {
-    input
-        .lines()
-        .map(|line| Vec::from(line.as_bytes()))
-        .collect()
+fn parse_input_day3(input: &str) -> Result<Grid<char>, ParseError> {
+    let source = Source::new(3, input);
+    Grid::parse(&source, input, "`.` or `#`", |c| {
+        matches!(c, '.' | '#').then_some(c)
+    })
 }
 
 fn _solve_functional(input: &str, dx: usize, dy: usize) -> usize {
@@ -13,7 +18,7 @@ fn _solve_functional(input: &str, dx: usize, dy: usize) -> usize {
         .lines()
         .step_by(dy)
         .zip(
-            (0..input.find('\n').unwrap_or_else(|| input.len()))
+            (0..input.find('\n').unwrap_or(input.len()))
                 .cycle()
                 .step_by(dx),
         )
@@ -21,31 +26,28 @@ fn _solve_functional(input: &str, dx: usize, dy: usize) -> usize {
         .count()
 }
 
-fn solve_imperative(input: &[Vec<u8>], dx: usize, dy: usize) -> usize {
+fn solve_imperative(input: &Grid<char>, dx: usize, dy: usize) -> usize {
     let mut x = 0;
     let mut y = 0;
-    let width = input[0].len();
 
     let mut sum = 0;
-    while y < input.len() {
-        if input[y][x] as char == '#' {
+    while y < input.height() {
+        if *input.get_wrapping((y, x)) == '#' {
             sum += 1;
         }
         x += dx;
-        x %= width;
-
         y += dy;
     }
     sum
 }
 
 #[aoc(day3, part1)]
-fn day3_part1(input: &[Vec<u8>]) -> usize {
+fn day3_part1(input: &Grid<char>) -> usize {
     solve_imperative(input, 3, 1)
 }
 
 #[aoc(day3, part2)]
-fn day3_part2(input: &[Vec<u8>]) -> usize {
+fn day3_part2(input: &Grid<char>) -> usize {
     solve_imperative(input, 1, 1)
         * solve_imperative(input, 3, 1)
         * solve_imperative(input, 5, 1)
@@ -60,12 +62,12 @@ mod tests {
     #[test]
     fn test_part1() {
         let input = "..##.......\n#...#...#..\n.#....#..#.\n..#.#...#.#\n.#...##..#.\n..#.##.....\n.#.#.#....#\n.#........#\n#.##...#...\n#...##....#\n.#..#...#.#";
-        assert_eq!(day3_part1(&parse_input_day3(&input)), 7);
+        assert_eq!(day3_part1(&parse_input_day3(input).unwrap()), 7);
     }
 
     #[test]
     fn test_part2() {
         let input = "..##.......\n#...#...#..\n.#....#..#.\n..#.#...#.#\n.#...##..#.\n..#.##.....\n.#.#.#....#\n.#........#\n#.##...#...\n#...##....#\n.#..#...#.#";
-        assert_eq!(day3_part2(&parse_input_day3(&input)), 336);
+        assert_eq!(day3_part2(&parse_input_day3(input).unwrap()), 336);
     }
 }
@@ -1,18 +1,40 @@
 use std::collections::HashMap;
 
-use aoc_runner_derive::aoc;
-use itertools::Itertools;
+use aoc_runner_derive::{aoc, aoc_generator};
 use regex::Regex;
 
-#[aoc(day4, part1)]
-fn day4_part1(input: &str) -> i32 {
+use crate::parse::{ParseError, Source};
+
+type Passport = HashMap<String, String>;
+
+#[aoc_generator(day4)]
+fn parse_input_day4(input: &str) -> Result<Vec<Passport>, ParseError> {
+    let source = Source::new(4, input);
     input
         .split("\n\n")
-        .map(|passport_data| passport_data.replace('\n', " "))
-        .map(|passport| match passport.split(' ').count() {
+        .map(|passport| {
+            passport
+                .split_whitespace()
+                .map(|field| {
+                    let mut parts = field.splitn(2, ':');
+                    match (parts.next(), parts.next()) {
+                        (Some(key), Some(value)) => Ok((key.to_string(), value.to_string())),
+                        _ => Err(source.error(field, "a `key:value` field")),
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[aoc(day4, part1)]
+fn day4_part1(passports: &[Passport]) -> i32 {
+    passports
+        .iter()
+        .map(|passport| match passport.len() {
             8 => 1,
             7 => {
-                if passport.contains("cid:") {
+                if passport.contains_key("cid") {
                     0
                 } else {
                     1
@@ -24,19 +46,13 @@ fn day4_part1(input: &str) -> i32 {
 }
 
 #[aoc(day4, part2)]
-fn day4_part2(input: &str) -> i32 {
+fn day4_part2(passports: &[Passport]) -> i32 {
     let hcl_reg = Regex::new(r"^#[0-9a-f]{6}$").unwrap();
     let allowed_ecl = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
 
-    input
-        .split("\n\n")
-        .map(|passport_data| passport_data.replace('\n', " "))
-        .map(|passport| {
-            let map = passport
-                .split(' ')
-                .map(|field| field.splitn(2, ':').collect_tuple().unwrap())
-                .collect::<HashMap<&str, &str>>();
-
+    passports
+        .iter()
+        .map(|map| {
             match map.get("byr") {
                 None => return 0,
                 Some(value) => {
@@ -44,7 +60,7 @@ fn day4_part2(input: &str) -> i32 {
                         return 0;
                     }
                     let num = value.parse::<i32>().unwrap_or(0);
-                    if num < 1920 || num > 2002 {
+                    if !(1920..=2002).contains(&num) {
                         return 0;
                     }
                 }
@@ -57,7 +73,7 @@ fn day4_part2(input: &str) -> i32 {
                         return 0;
                     }
                     let num = value.parse::<i32>().unwrap_or(0);
-                    if num < 2010 || num > 2020 {
+                    if !(2010..=2020).contains(&num) {
                         return 0;
                     }
                 }
@@ -70,7 +86,7 @@ fn day4_part2(input: &str) -> i32 {
                         return 0;
                     }
                     let num = value.parse::<i32>().unwrap_or(0);
-                    if num < 2020 || num > 2030 {
+                    if !(2020..=2030).contains(&num) {
                         return 0;
                     }
                 }
@@ -79,13 +95,12 @@ fn day4_part2(input: &str) -> i32 {
             match map.get("hgt") {
                 None => return 0,
                 Some(value) => {
-                    let v = value[..value.len() - 2].parse::<i32>().unwrap_or(0);
-                    if value.ends_with("cm") {
-                        if v < 150 || v > 193 {
+                    if let Some(cm) = value.strip_suffix("cm") {
+                        if !(150..=193).contains(&cm.parse::<i32>().unwrap_or(0)) {
                             return 0;
                         }
-                    } else if value.ends_with("in") {
-                        if v < 59 || v > 76 {
+                    } else if let Some(inches) = value.strip_suffix("in") {
+                        if !(59..=76).contains(&inches.parse::<i32>().unwrap_or(0)) {
                             return 0;
                         }
                     } else {
@@ -106,7 +121,7 @@ fn day4_part2(input: &str) -> i32 {
             match map.get("ecl") {
                 None => return 0,
                 Some(value) => {
-                    if !allowed_ecl.contains(value) {
+                    if !allowed_ecl.contains(&value.as_str()) {
                         return 0;
                     }
                 }
@@ -146,7 +161,7 @@ hgt:179cm
 hcl:#cfa07d eyr:2025 pid:166559648
 iyr:2011 ecl:brn hgt:59in";
 
-        assert_eq!(day4_part1(input), 2);
+        assert_eq!(day4_part1(&parse_input_day4(input).unwrap()), 2);
     }
 
     #[test]
@@ -166,6 +181,6 @@ iyr:2011 ecl:brn hgt:59in";
             pid:3556412378 byr:2007"
             .replace("            ", "");
 
-        assert_eq!(day4_part2(&input), 0);
+        assert_eq!(day4_part2(&parse_input_day4(&input).unwrap()), 0);
     }
 }
@@ -1,4 +1,6 @@
-use aoc_runner_derive::aoc;
+use aoc_runner_derive::{aoc, aoc_generator};
+
+use crate::parse::{ParseError, Source};
 
 fn code_to_id(code: &str) -> usize {
     let binary = code
@@ -9,20 +11,39 @@ fn code_to_id(code: &str) -> usize {
     usize::from_str_radix(&binary, 2).unwrap_or(0)
 }
 
-#[aoc(day5, part1)]
-fn day5_part1(input: &str) -> Option<usize> {
+#[aoc_generator(day5)]
+fn parse_input_day5(input: &str) -> Result<Vec<usize>, ParseError> {
+    let source = Source::new(5, input);
     input
         .lines()
-        .map(|line| code_to_id(&line[..7]) * 8 + code_to_id(&line[7..]))
-        .max()
+        .map(|line| {
+            // 7 row letters followed by 3 column letters
+            for (index, c) in line.char_indices() {
+                let (valid, expected) = if index < 7 {
+                    (c == 'F' || c == 'B', "`F` or `B`")
+                } else {
+                    (c == 'L' || c == 'R', "`L` or `R`")
+                };
+                if !valid {
+                    return Err(source.error(&line[index..index + c.len_utf8()], expected));
+                }
+            }
+            if line.len() != 10 {
+                return Err(source.error(line, "a 10 letter boarding pass"));
+            }
+            Ok(code_to_id(&line[..7]) * 8 + code_to_id(&line[7..]))
+        })
+        .collect()
+}
+
+#[aoc(day5, part1)]
+fn day5_part1(ids: &[usize]) -> Option<usize> {
+    ids.iter().max().copied()
 }
 
 #[aoc(day5, part2)]
-fn day5_part2(input: &str) -> Option<usize> {
-    let mut ids = input
-        .lines()
-        .map(|line| code_to_id(&line[..7]) * 8 + code_to_id(&line[7..]))
-        .collect::<Vec<usize>>();
+fn day5_part2(ids: &[usize]) -> Option<usize> {
+    let mut ids = ids.to_vec();
 
     ids.sort_unstable();
     for window in ids.windows(2) {
@@ -39,9 +60,24 @@ mod tests {
 
     #[test]
     fn test_part1() {
-        assert_eq!(day5_part1("FBFBBFFRLR"), Some(357));
-        assert_eq!(day5_part1("BFFFBBFRRR"), Some(567));
-        assert_eq!(day5_part1("FFFBBBFRRR"), Some(119));
-        assert_eq!(day5_part1("BBFFBBFRLL"), Some(820));
+        assert_eq!(
+            day5_part1(&parse_input_day5("FBFBBFFRLR").unwrap()),
+            Some(357)
+        );
+        assert_eq!(
+            day5_part1(&parse_input_day5("BFFFBBFRRR").unwrap()),
+            Some(567)
+        );
+        assert_eq!(
+            day5_part1(&parse_input_day5("FFFBBBFRRR").unwrap()),
+            Some(119)
+        );
+        assert_eq!(
+            day5_part1(&parse_input_day5("BBFFBBFRLL").unwrap()),
+            Some(820)
+        );
+
+        let error = parse_input_day5("BBFFBBFRLL\nBBFFBXFRLL").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 6));
     }
 }
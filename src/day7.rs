@@ -4,6 +4,8 @@ use aoc_runner_derive::{aoc, aoc_generator};
 
 use regex::Regex;
 
+use crate::parse::{ParseError, Source};
+
 // A Bag can contain 'count' bags of color 'color'
 #[derive(Debug)]
 struct Node {
@@ -18,31 +20,35 @@ impl Node {
 }
 
 #[aoc_generator(day7)]
-fn parse_input_day7(input: &str) -> HashMap<String, Vec<Node>> {
-    let all_regex = Regex::new(r"([\s\w]+?) bags contain ([\s\w,]+?)\.\n?").unwrap();
-    let sentence_regex = Regex::new(r"(\d+) ([\s\w]+?) bag").unwrap();
+fn parse_input_day7(input: &str) -> Result<HashMap<String, Vec<Node>>, ParseError> {
+    let source = Source::new(7, input);
+    let all_regex = Regex::new(r"^([\s\w]+?) bags contain ([\s\w,]+?)\.$").unwrap();
+    let sentence_regex = Regex::new(r"^(\d+) ([\s\w]+?) bags?$").unwrap();
 
     let mut graph = HashMap::<String, Vec<Node>>::new();
-    all_regex
-        .captures_iter(input)
-        .map(|capture| {
-            capture[2]
-                .split(", ")
-                .map(|sentence| {
-                    sentence_regex
-                        .captures_iter(sentence)
-                        .map(|edge| {
-                            graph
-                                .entry(capture[1].to_string())
-                                .or_default()
-                                .push(Node::new(edge[1].parse().unwrap(), edge[2].to_string()));
-                        })
-                        .for_each(drop);
-                })
-                .for_each(drop);
-        })
-        .for_each(drop);
-    graph
+    for line in input.lines() {
+        let capture = all_regex
+            .captures(line)
+            .ok_or_else(|| source.error(line, "a `<color> bags contain <contents>.` rule"))?;
+        let contents = capture.get(2).unwrap().as_str();
+        if contents == "no other bags" {
+            continue;
+        }
+
+        for sentence in contents.split(", ") {
+            let edge = sentence_regex
+                .captures(sentence)
+                .ok_or_else(|| source.error(sentence, "a `<count> <color> bags` clause"))?;
+            graph
+                .entry(capture[1].to_string())
+                .or_default()
+                .push(Node::new(
+                    source.parse(edge.get(1).unwrap().as_str(), "a bag count")?,
+                    edge[2].to_string(),
+                ));
+        }
+    }
+    Ok(graph)
 }
 
 fn reverse_graph(graph: &HashMap<String, Vec<Node>>) -> HashMap<String, Vec<Node>> {
@@ -122,7 +128,7 @@ dark olive bags contain 3 faded blue bags, 4 dotted black bags.
 vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
 faded blue bags contain no other bags.
 dotted black bags contain no other bags.";
-        assert_eq!(day7_part1(&parse_input_day7(input)), Some(4));
+        assert_eq!(day7_part1(&parse_input_day7(input).unwrap()), Some(4));
     }
 
     #[test]
@@ -134,6 +140,6 @@ dark yellow bags contain 2 dark green bags.
 dark green bags contain 2 dark blue bags.
 dark blue bags contain 2 dark violet bags.
 dark violet bags contain no other bags.";
-        assert_eq!(day7_part2(&parse_input_day7(input)), Some(126));
+        assert_eq!(day7_part2(&parse_input_day7(input).unwrap()), Some(126));
     }
 }
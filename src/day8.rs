@@ -1,97 +1,170 @@
-use aoc_runner_derive::aoc;
-use itertools::Itertools;
-
-#[derive(Debug, Clone)]
-enum OpCode {
-    NOP,
-    JMP,
-    ACC,
+use std::{convert::TryFrom, error::Error, fmt};
+
+use aoc_runner_derive::{aoc, aoc_generator};
+
+use crate::{
+    console::{Halt, Instruction, InstructionSet, Machine, Overflow, Registers},
+    parse::{ParseError, Source},
+};
+
+/// The instruction to swap for the program to terminate, and the
+/// accumulator it then terminates with.
+#[derive(Debug, PartialEq)]
+struct Repair {
+    index: usize,
+    accumulator: i64,
 }
 
-impl OpCode {
-    fn from(opcode_string: &str) -> Self {
-        match opcode_string {
-            "nop" => OpCode::NOP,
-            "jmp" => OpCode::JMP,
-            "acc" => OpCode::ACC,
-            _ => unreachable!(),
+#[derive(Debug, PartialEq)]
+enum RepairError {
+    /// The instruction set has no operation for the opcode at `index`.
+    UnknownOpcode { index: usize },
+    /// Running the repaired program overflows a register at `index`.
+    Overflow { index: usize },
+    /// No single swap makes the program terminate.
+    NotRepairable,
+}
+
+impl fmt::Display for RepairError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownOpcode { index } => {
+                write!(f, "instruction {} has an unknown opcode", index)
+            }
+            Self::Overflow { index } => write!(f, "instruction {} overflows a register", index),
+            Self::NotRepairable => write!(f, "no single swap makes the program terminate"),
         }
     }
 }
 
-#[derive(Debug, Clone)]
-struct Instruction {
-    opcode: OpCode,
-    value: isize,
+impl Error for RepairError {}
+
+/// The opcode `opcode` can be swapped with, if the set knows both.
+fn swapped(set: &InstructionSet, opcode: &str) -> Option<&'static str> {
+    set.get(opcode)?
+        .swapped_with()
+        .filter(|&other| set.get(other).is_some())
+}
+
+/// Runs `opcode` at `index` on its own. Control flow has to be the same
+/// whatever the accumulator holds for the repair to work.
+fn execute(
+    set: &InstructionSet,
+    index: usize,
+    opcode: &str,
+    argument: i64,
+    accumulator: i64,
+) -> Result<Registers, Overflow> {
+    let mut registers = Registers {
+        pc: index as i64,
+        accumulator,
+    };
+    set.get(opcode)
+        .expect("opcodes are checked before repairing")
+        .execute(argument, &mut registers)?;
+    Ok(registers)
+}
+
+/// Where `opcode` at `index` goes next, none when that's before the start
+/// or past what `pc` can hold.
+fn target(set: &InstructionSet, index: usize, opcode: &str, argument: i64) -> Option<usize> {
+    let registers = execute(set, index, opcode, argument, 0).ok()?;
+    usize::try_from(registers.pc).ok()
 }
 
-impl Instruction {
-    fn new(opcode: &str, value: isize) -> Self {
-        Self {
-            opcode: OpCode::from(opcode),
-            value,
+/// Marks every instruction from which the unmodified program runs off its
+/// end, by walking the control-flow graph backwards from the end.
+fn reaching_end(set: &InstructionSet, instructions: &[Instruction]) -> Vec<bool> {
+    let end = instructions.len();
+    let mut predecessors = vec![Vec::new(); end + 1];
+    for (index, instruction) in instructions.iter().enumerate() {
+        if let Some(next) = target(set, index, instruction.opcode, instruction.argument) {
+            if next <= end {
+                predecessors[next].push(index);
+            }
         }
     }
-}
 
-fn parse(input: &str) -> Vec<Instruction> {
-    input
-        .lines()
-        .map(|line| line.splitn(2, ' ').collect_tuple().unwrap())
-        .map(|(opcode, value)| Instruction::new(&opcode, value.parse::<isize>().unwrap()))
-        .collect::<Vec<_>>()
+    let mut reaches = vec![false; end + 1];
+    reaches[end] = true;
+    let mut stack = vec![end];
+    while let Some(node) = stack.pop() {
+        for &previous in predecessors[node].iter() {
+            if !reaches[previous] {
+                reaches[previous] = true;
+                stack.push(previous);
+            }
+        }
+    }
+    reaches
 }
 
-fn execute(instructions: &[Instruction]) -> Option<(usize, usize)> {
+/// Follows the program once, swapping the first instruction whose other
+/// branch lands on a path to the end, so the whole repair is linear.
+fn repair(set: &InstructionSet, instructions: &[Instruction]) -> Result<Repair, RepairError> {
+    if let Some(index) = instructions
+        .iter()
+        .position(|instruction| set.get(instruction.opcode).is_none())
+    {
+        return Err(RepairError::UnknownOpcode { index });
+    }
+
+    let reaches = reaching_end(set, instructions);
+    let lands = |next: Option<usize>| next.and_then(|next| reaches.get(next)) == Some(&true);
+
+    let mut visited = vec![false; instructions.len()];
+    let mut patched = None;
     let mut accumulator = 0;
-    let mut pc: isize = 0;
-    let mut visits = vec![false; instructions.len()];
-    while let Some(instruction) = instructions.get(pc as usize) {
-        if visits[pc as usize] {
-            break;
+    let mut pc = 0;
+    while pc != instructions.len() {
+        let instruction = instructions.get(pc).ok_or(RepairError::NotRepairable)?;
+        if std::mem::replace(&mut visited[pc], true) {
+            return Err(RepairError::NotRepairable);
+        }
+
+        let mut opcode = instruction.opcode;
+        if let (None, Some(other)) = (patched, swapped(set, opcode)) {
+            if lands(target(set, pc, other, instruction.argument)) {
+                patched = Some(pc);
+                opcode = other;
+            }
         }
-        let mut pc_diff = 1;
-        match instruction.opcode {
-            OpCode::ACC => accumulator += instruction.value,
-            OpCode::JMP => pc_diff = instruction.value,
-            OpCode::NOP => {}
-        };
-        visits[pc as usize] = true;
-        pc += pc_diff;
+        let registers = execute(set, pc, opcode, instruction.argument, accumulator)
+            .map_err(|_| RepairError::Overflow { index: pc })?;
+        accumulator = registers.accumulator;
+        pc = usize::try_from(registers.pc).map_err(|_| RepairError::NotRepairable)?;
     }
-    Some((pc as usize, accumulator as usize))
+
+    Ok(Repair {
+        index: patched.ok_or(RepairError::NotRepairable)?,
+        accumulator,
+    })
+}
+
+#[aoc_generator(day8)]
+fn parse_input_day8(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    InstructionSet::handheld().parse(&Source::new(8, input), input)
 }
 
 #[aoc(day8, part1)]
-fn day8_part1(input: &str) -> Option<usize> {
-    let instructions = parse(input);
-    Some(execute(&instructions).unwrap().1)
+fn day8_part1(instructions: &[Instruction]) -> Option<i64> {
+    let set = InstructionSet::handheld();
+    let mut machine = Machine::new(&set, instructions);
+    match machine.run() {
+        Halt::InfiniteLoop { .. } => Some(machine.registers().accumulator),
+        _ => None,
+    }
 }
 
 #[aoc(day8, part2)]
-fn day8_part2(input: &str) -> Option<usize> {
-    let mut instructions = parse(input);
-
-    for index in 0..instructions.len() {
-        let old_opcode = instructions[index].opcode.clone();
-        instructions[index].opcode = match instructions[index].opcode {
-            OpCode::JMP => OpCode::NOP,
-            OpCode::NOP => OpCode::JMP,
-            _ => continue,
-        };
-        if let Some((last_pc, value)) = execute(&instructions) {
-            if last_pc >= instructions.len() {
-                return Some(value);
-            }
-        }
-        instructions[index].opcode = old_opcode;
-    }
-    None
+fn day8_part2(instructions: &[Instruction]) -> Result<i64, RepairError> {
+    repair(&InstructionSet::handheld(), instructions).map(|repair| repair.accumulator)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::console::Operation;
 
     #[test]
     fn test_part1() {
@@ -104,7 +177,7 @@ acc -99
 acc +1
 jmp -4
 acc +6";
-        assert_eq!(day8_part1(input), Some(5));
+        assert_eq!(day8_part1(&parse_input_day8(input).unwrap()), Some(5));
     }
 
     #[test]
@@ -118,6 +191,69 @@ acc -99
 acc +1
 jmp -4
 acc +6";
-        assert_eq!(day8_part2(input), Some(8));
+        assert_eq!(day8_part2(&parse_input_day8(input).unwrap()), Ok(8));
+        assert_eq!(
+            repair(
+                &InstructionSet::handheld(),
+                &parse_input_day8(input).unwrap()
+            ),
+            Ok(Repair {
+                index: 7,
+                accumulator: 8
+            })
+        );
+    }
+
+    #[test]
+    fn test_repair_with_custom_opcodes() {
+        // Jumps over the next instruction, and can't be swapped
+        struct Skp;
+
+        impl Operation for Skp {
+            fn mnemonic(&self) -> &'static str {
+                "skp"
+            }
+
+            fn execute(&self, _: i64, registers: &mut Registers) -> Result<(), Overflow> {
+                registers.jump(2)
+            }
+        }
+
+        let mut set = InstructionSet::handheld();
+        set.register(Skp);
+        let text = "skp +0\nacc +5\njmp +0";
+        let program = set.parse(&Source::new(8, text), text).unwrap();
+        assert_eq!(
+            repair(&set, &program),
+            Ok(Repair {
+                index: 2,
+                accumulator: 0
+            })
+        );
+        assert_eq!(
+            repair(&InstructionSet::handheld(), &program),
+            Err(RepairError::UnknownOpcode { index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_repair_overflow() {
+        let set = InstructionSet::handheld();
+        let text = "jmp +9223372036854775807\nacc +1";
+        let program = parse_input_day8(text).unwrap();
+        assert_eq!(
+            repair(&set, &program),
+            Ok(Repair {
+                index: 0,
+                accumulator: 1
+            })
+        );
+
+        let text = "acc +9223372036854775807\nacc +1";
+        let program = parse_input_day8(text).unwrap();
+        assert_eq!(
+            repair(&set, &program),
+            Err(RepairError::Overflow { index: 1 })
+        );
     }
 }
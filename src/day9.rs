@@ -1,12 +1,15 @@
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::cmp::Ordering;
 
+use crate::parse::{ParseError, Source};
+
 #[aoc_generator(day9)]
-fn parse_input_day9(input: &str) -> Vec<usize> {
+fn parse_input_day9(input: &str) -> Result<Vec<usize>, ParseError> {
+    let source = Source::new(9, input);
     input
         .lines()
-        .map(|line| line.parse::<usize>().unwrap())
-        .collect::<Vec<_>>()
+        .map(|line| source.parse(line, "a number"))
+        .collect()
 }
 
 #[aoc(day9, part1)]
@@ -93,7 +96,7 @@ mod test {
 277
 309
 576";
-        assert_eq!(solve_part1(&parse_input_day9(input), 5), Some(127));
+        assert_eq!(solve_part1(&parse_input_day9(input).unwrap(), 5), Some(127));
     }
 
     #[test]
@@ -118,6 +121,6 @@ mod test {
 277
 309
 576";
-        assert_eq!(solve_part2(&parse_input_day9(input), 5), Some(62));
+        assert_eq!(solve_part2(&parse_input_day9(input).unwrap(), 5), Some(62));
     }
 }
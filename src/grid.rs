@@ -0,0 +1,264 @@
+use std::{
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+use crate::parse::{ParseError, Source};
+
+/// `(row, column)` of a cell.
+pub type Position = (usize, usize);
+
+/// Up, right, down and left as `(row, column)` steps.
+pub const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+/// The orthogonal steps and the four diagonals, clockwise from up.
+pub const ALL_DIRECTIONS: [(isize, isize); 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+/// A dense rectangle of cells stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn from_fn(width: usize, height: usize, f: impl FnMut(Position) -> T) -> Self {
+        let cells = (0..height)
+            .flat_map(|row| (0..width).map(move |column| (row, column)))
+            .map(f)
+            .collect();
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Reads one row per line, `cell` turns each char into a cell
+    /// or rejects it, in which case the error asks for `expected`.
+    pub fn parse(
+        source: &Source,
+        text: &str,
+        expected: &'static str,
+        cell: impl Fn(char) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = Vec::with_capacity(text.len());
+        for line in text.lines() {
+            let before = cells.len();
+            for (index, c) in line.char_indices() {
+                let value = cell(c)
+                    .ok_or_else(|| source.error(&line[index..index + c.len_utf8()], expected))?;
+                cells.push(value);
+            }
+
+            let line_width = cells.len() - before;
+            if *width.get_or_insert(line_width) != line_width {
+                return Err(source.error(line, "a row as wide as the first one"));
+            }
+            height += 1;
+        }
+
+        Ok(Self {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, (row, column): Position) -> Option<&T> {
+        if row < self.height && column < self.width {
+            Some(&self.cells[row * self.width + column])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, (row, column): Position) -> Option<&mut T> {
+        if row < self.height && column < self.width {
+            Some(&mut self.cells[row * self.width + column])
+        } else {
+            None
+        }
+    }
+
+    /// Like `get`, but the grid repeats forever to the right and to the bottom.
+    pub fn get_wrapping(&self, (row, column): Position) -> &T {
+        &self[(row % self.height, column % self.width)]
+    }
+
+    /// The position one `step` away, if it's still inside the grid.
+    pub fn offset(&self, (row, column): Position, (dr, dc): (isize, isize)) -> Option<Position> {
+        let row = row.checked_add_signed(dr)?;
+        let column = column.checked_add_signed(dc)?;
+        if row < self.height && column < self.width {
+            Some((row, column))
+        } else {
+            None
+        }
+    }
+
+    pub fn neighbors4(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        ORTHOGONAL
+            .iter()
+            .filter_map(move |&step| self.offset(position, step))
+    }
+
+    pub fn neighbors8(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        ALL_DIRECTIONS
+            .iter()
+            .filter_map(move |&step| self.offset(position, step))
+    }
+
+    /// Every position met walking from `position` (excluded) by `step`
+    /// until falling off the grid.
+    pub fn ray(
+        &self,
+        position: Position,
+        step: (isize, isize),
+    ) -> impl Iterator<Item = Position> + '_ {
+        std::iter::successors(self.offset(position, step), move |&current| {
+            self.offset(current, step)
+        })
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        let width = self.width;
+        (0..self.cells.len()).map(move |index| (index / width, index % width))
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.cells.iter()
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    pub fn column(&self, column: usize) -> impl Iterator<Item = &T> {
+        self.rows().map(move |row| &row[column])
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    /// Turns the grid 90° clockwise.
+    pub fn rotate(&self) -> Self {
+        Self::from_fn(self.height, self.width, |(row, column)| {
+            self[(self.height - 1 - column, row)].clone()
+        })
+    }
+
+    /// Mirrors the grid left to right.
+    pub fn flip(&self) -> Self {
+        Self::from_fn(self.width, self.height, |(row, column)| {
+            self[(row, self.width - 1 - column)].clone()
+        })
+    }
+
+    /// All 8 rotations and reflections of the grid.
+    pub fn orientations(&self) -> Vec<Self> {
+        let mut result = Vec::with_capacity(8);
+        let mut current = self.clone();
+        for _ in 0..4 {
+            let next = current.rotate();
+            result.push(current.flip());
+            result.push(current);
+            current = next;
+        }
+        result
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: Position) -> &T {
+        self.get(position).expect("position outside of the grid")
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, position: Position) -> &mut T {
+        self.get_mut(position)
+            .expect("position outside of the grid")
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row.iter() {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Grid<char> {
+        Grid::parse(&Source::new(0, text), text, "a cell", Some).unwrap()
+    }
+
+    #[test]
+    fn test_neighbors_and_rays() {
+        let grid = parse("abc\ndef\nghi");
+        assert_eq!(grid.neighbors4((0, 0)).count(), 2);
+        assert_eq!(grid.neighbors8((1, 1)).count(), 8);
+        assert_eq!(
+            grid.ray((0, 0), (1, 1))
+                .map(|p| grid[p])
+                .collect::<String>(),
+            "ei"
+        );
+        assert_eq!(*grid.get_wrapping((3, 7)), 'b');
+        assert_eq!(grid.get((3, 0)), None);
+    }
+
+    #[test]
+    fn test_rotate_and_flip() {
+        let grid = parse("ab\ncd\nef");
+        assert_eq!(grid.rotate().to_string(), "eca\nfdb\n");
+        assert_eq!(grid.flip().to_string(), "ba\ndc\nfe\n");
+        assert_eq!(grid.orientations().len(), 8);
+
+        let text = "ab\ncde";
+        let error = Grid::parse(&Source::new(0, text), text, "a cell", Some).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 1));
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    life::{Sparse, Topology},
+    parse::{ParseError, Source},
+};
+
+/// The six neighbours of a tile on a grid of pointy-topped hexagons.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    East,
+    SouthEast,
+    SouthWest,
+    West,
+    NorthWest,
+    NorthEast,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 6] = [
+        Direction::East,
+        Direction::SouthEast,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+        Direction::NorthEast,
+    ];
+
+    fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::East => (1, 0),
+            Direction::SouthEast => (0, 1),
+            Direction::SouthWest => (-1, 1),
+            Direction::West => (-1, 0),
+            Direction::NorthWest => (0, -1),
+            Direction::NorthEast => (1, -1),
+        }
+    }
+
+    /// Parses a run of `e`, `se`, `sw`, `w`, `nw` and `ne` with no delimiters.
+    pub fn parse_path(source: &Source, path: &str) -> Result<Vec<Direction>, ParseError> {
+        let mut directions = Vec::new();
+        let mut rest = path;
+        while !rest.is_empty() {
+            let (direction, length) = match rest.get(..2) {
+                Some("se") => (Direction::SouthEast, 2),
+                Some("sw") => (Direction::SouthWest, 2),
+                Some("nw") => (Direction::NorthWest, 2),
+                Some("ne") => (Direction::NorthEast, 2),
+                _ if rest.starts_with('e') => (Direction::East, 1),
+                _ if rest.starts_with('w') => (Direction::West, 1),
+                _ => {
+                    let end = rest.char_indices().nth(2).map_or(rest.len(), |(i, _)| i);
+                    return Err(
+                        source.error(&rest[..end], "one of `e`, `se`, `sw`, `w`, `nw` or `ne`")
+                    );
+                }
+            };
+            directions.push(direction);
+            rest = &rest[length..];
+        }
+        Ok(directions)
+    }
+}
+
+/// A hexagon in axial coordinates, `r` grows to the south east
+/// and `q` grows to the east.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Hex {
+    q: isize,
+    r: isize,
+}
+
+impl Hex {
+    pub fn origin() -> Self {
+        Self::default()
+    }
+
+    pub fn step(self, direction: Direction) -> Self {
+        let (dq, dr) = direction.delta();
+        Hex {
+            q: self.q + dq,
+            r: self.r + dr,
+        }
+    }
+
+    pub fn walk(self, path: &[Direction]) -> Self {
+        path.iter()
+            .fold(self, |hex, &direction| hex.step(direction))
+    }
+
+    pub fn neighbors(self) -> impl Iterator<Item = Hex> {
+        Direction::ALL
+            .iter()
+            .map(move |&direction| self.step(direction))
+    }
+}
+
+/// The endless floor of hexagonal tiles, as a `life` topology.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tiling;
+
+impl Topology for Tiling {
+    type Cell = Hex;
+    type State = Sparse<Hex>;
+
+    fn neighbors(&self, hex: &Hex) -> Vec<Hex> {
+        hex.neighbors().collect()
+    }
+}
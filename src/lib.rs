@@ -1,20 +1,25 @@
 use aoc_runner_derive::aoc_lib;
 
+pub mod console;
+mod crt;
 mod day1;
 mod day10;
-mod day11;
-mod day12;
+pub mod day11;
+pub mod day12;
 mod day13;
 mod day14;
 mod day15;
-mod day16;
-mod day17;
-mod day18;
+pub mod day16;
+pub mod day17;
+pub mod day18;
 mod day19;
 mod day2;
-mod day20;
+pub mod day20;
 mod day21;
 mod day22;
+mod day23;
+mod day24;
+mod day25;
 mod day3;
 mod day4;
 mod day5;
@@ -22,5 +27,11 @@ mod day6;
 mod day7;
 mod day8;
 mod day9;
+pub mod grid;
+mod hex;
+pub mod life;
+mod modular;
+pub mod parse;
+pub mod render;
 
 aoc_lib! { year = 2020 }
@@ -0,0 +1,661 @@
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, HashSet},
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use crate::grid::{Grid, Position, ALL_DIRECTIONS};
+
+/// The cells of an automaton and which of them are next to each other.
+pub trait Topology: Sized {
+    type Cell: Clone + Eq + Hash + fmt::Debug;
+    /// How the live cells are kept, `Sparse` unless the cells are known
+    /// up front.
+    type State: State<Self>;
+
+    /// The cells that count `cell` as a live neighbour, repeated as many
+    /// times as they count it.
+    fn neighbors(&self, cell: &Self::Cell) -> Vec<Self::Cell>;
+
+    /// How many cells `cell` stands for, when only one of several cells
+    /// that always share a state is stored.
+    fn weight(&self, _: &Self::Cell) -> usize {
+        1
+    }
+
+    /// Every cell `cell` stands for, itself included.
+    fn images(&self, cell: &Self::Cell) -> Vec<Self::Cell> {
+        vec![cell.clone()]
+    }
+
+    /// Every cell, for topologies that have a finite number of them. Rules
+    /// that bring cells to life with no live neighbours need it, as nothing
+    /// else points the engine at those cells.
+    fn cells(&self) -> Option<Vec<Self::Cell>> {
+        None
+    }
+}
+
+/// Unbounded space in `N` dimensions, cells touch along every axis and
+/// diagonal.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Space<const N: usize>;
+
+impl<const N: usize> Topology for Space<N> {
+    type Cell = [isize; N];
+    type State = Sparse<[isize; N]>;
+
+    fn neighbors(&self, cell: &[isize; N]) -> Vec<[isize; N]> {
+        // Every offset in {-1, 0, 1}^N read as a base 3 number
+        let total = 3_usize.pow(N as u32);
+        let mut neighbors = Vec::with_capacity(total - 1);
+        for index in 0..total {
+            let mut coordinates = *cell;
+            let mut rest = index;
+            for coordinate in coordinates.iter_mut() {
+                *coordinate += (rest % 3) as isize - 1;
+                rest /= 3;
+            }
+            if coordinates != *cell {
+                neighbors.push(coordinates);
+            }
+        }
+        neighbors
+    }
+}
+
+/// `Space` for patterns that don't change when any axis past the first
+/// two is mirrored, like those that start flat along those axes. Only cells
+/// with no negative coordinate on those axes are stored, halving the work
+/// per extra axis.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MirroredSpace<const N: usize>;
+
+impl<const N: usize> Topology for MirroredSpace<N> {
+    type Cell = [isize; N];
+    type State = Sparse<[isize; N]>;
+
+    fn neighbors(&self, cell: &[isize; N]) -> Vec<[isize; N]> {
+        // Where each axis can go, and how many mirror images of the cell get
+        // there: at 1, both the cell and its image at -1 are next to 0
+        let mut targets = vec![(*cell, 1)];
+        for (axis, &value) in cell.iter().enumerate() {
+            let steps: &[(isize, usize)] = match value {
+                0 if axis >= 2 => &[(0, 1), (1, 1)],
+                1 if axis >= 2 => &[(-1, 2), (0, 1), (1, 1)],
+                _ => &[(-1, 1), (0, 1), (1, 1)],
+            };
+            targets = targets
+                .into_iter()
+                .flat_map(|(target, count)| {
+                    steps.iter().map(move |&(step, times)| {
+                        let mut target = target;
+                        target[axis] += step;
+                        (target, count * times)
+                    })
+                })
+                .collect();
+        }
+
+        targets
+            .into_iter()
+            .filter(|(target, _)| target != cell)
+            .flat_map(|(target, count)| std::iter::repeat_n(target, count))
+            .collect()
+    }
+
+    fn weight(&self, cell: &[isize; N]) -> usize {
+        1 << cell[2..].iter().filter(|&&value| value != 0).count()
+    }
+
+    fn images(&self, cell: &[isize; N]) -> Vec<[isize; N]> {
+        let mut images = vec![*cell];
+        for axis in (2..N).filter(|&axis| cell[axis] != 0) {
+            let mirrored = images
+                .iter()
+                .map(|&image| {
+                    let mut image = image;
+                    image[axis] = -image[axis];
+                    image
+                })
+                .collect::<Vec<_>>();
+            images.extend(mirrored);
+        }
+        images
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Neighborhood {
+    /// The up to 8 cells around a cell.
+    Adjacent,
+    /// The first cell seen in each of the 8 directions.
+    LineOfSight,
+}
+
+/// A bounded rectangle where only some positions hold cells, the others
+/// are never alive and are seen through.
+#[derive(Debug, Clone)]
+pub struct Plane {
+    cells: Grid<bool>,
+    neighborhood: Neighborhood,
+}
+
+impl Plane {
+    pub fn new(cells: Grid<bool>, neighborhood: Neighborhood) -> Self {
+        Self {
+            cells,
+            neighborhood,
+        }
+    }
+}
+
+impl Topology for Plane {
+    type Cell = Position;
+    type State = Sparse<Position>;
+
+    fn neighbors(&self, &position: &Position) -> Vec<Position> {
+        match self.neighborhood {
+            Neighborhood::Adjacent => self
+                .cells
+                .neighbors8(position)
+                .filter(|&neighbor| self.cells[neighbor])
+                .collect(),
+            Neighborhood::LineOfSight => ALL_DIRECTIONS
+                .iter()
+                .filter_map(|&step| {
+                    self.cells
+                        .ray(position, step)
+                        .find(|&neighbor| self.cells[neighbor])
+                })
+                .collect(),
+        }
+    }
+
+    fn cells(&self) -> Option<Vec<Position>> {
+        Some(
+            self.cells
+                .positions()
+                .filter(|&position| self.cells[position])
+                .collect(),
+        )
+    }
+}
+
+/// The cells of a finite topology numbered in a given order, with who each
+/// of them counts as a neighbour worked out once: the neighbours of cell
+/// `i` are `neighbors[starts[i]..starts[i + 1]]`. Neighbours that weren't
+/// numbered are left out.
+#[derive(Debug, Clone)]
+pub struct Adjacency {
+    starts: Vec<usize>,
+    neighbors: Vec<usize>,
+}
+
+impl Adjacency {
+    pub fn new<T: Topology>(topology: &T, cells: &[T::Cell]) -> Self {
+        let index = cells
+            .iter()
+            .enumerate()
+            .map(|(number, cell)| (cell.clone(), number))
+            .collect::<HashMap<_, _>>();
+        let mut starts = vec![0];
+        let mut neighbors = Vec::new();
+        for cell in cells.iter() {
+            neighbors.extend(
+                topology
+                    .neighbors(cell)
+                    .iter()
+                    .filter_map(|neighbor| index.get(neighbor).copied()),
+            );
+            starts.push(neighbors.len());
+        }
+        Self { starts, neighbors }
+    }
+
+    pub fn len(&self) -> usize {
+        self.starts.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The neighbours of `cell`, without allocating.
+    pub fn row(&self, cell: usize) -> &[usize] {
+        &self.neighbors[self.starts[cell]..self.starts[cell + 1]]
+    }
+}
+
+impl Topology for Adjacency {
+    type Cell = usize;
+    type State = Dense;
+
+    fn neighbors(&self, &cell: &usize) -> Vec<usize> {
+        self.row(cell).to_vec()
+    }
+
+    fn cells(&self) -> Option<Vec<usize>> {
+        Some((0..self.len()).collect())
+    }
+}
+
+/// How many live neighbours bring a dead cell to life, and how many keep
+/// a live cell alive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    birth: Vec<usize>,
+    survive: Vec<usize>,
+}
+
+impl Rule {
+    pub fn new(birth: &[usize], survive: &[usize]) -> Self {
+        Self {
+            birth: birth.to_vec(),
+            survive: survive.to_vec(),
+        }
+    }
+
+    /// B3/S23, Conway's original game of life.
+    pub fn conway() -> Self {
+        Self::new(&[3], &[2, 3])
+    }
+
+    pub fn next(&self, alive: bool, neighbors: usize) -> bool {
+        if alive {
+            self.survive.contains(&neighbors)
+        } else {
+            self.birth.contains(&neighbors)
+        }
+    }
+}
+
+/// The live cells of a generation, and how they get to the next one.
+pub trait State<T: Topology>: Clone + fmt::Debug + PartialEq {
+    fn new(topology: &T, alive: impl IntoIterator<Item = T::Cell>) -> Self;
+
+    fn is_alive(&self, cell: &T::Cell) -> bool;
+
+    fn alive(&self) -> Vec<T::Cell>;
+
+    /// A hash of the live cells, the same for equal states.
+    fn fingerprint(&self) -> u64;
+
+    /// Moves to the next generation, returns how many cells changed.
+    fn step(&mut self, topology: &T, rule: &Rule) -> usize;
+}
+
+/// Only the live cells, for topologies that can grow without bound.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sparse<C: Eq + Hash> {
+    alive: HashSet<C>,
+}
+
+impl<T: Topology> State<T> for Sparse<T::Cell> {
+    fn new(_: &T, alive: impl IntoIterator<Item = T::Cell>) -> Self {
+        Self {
+            alive: alive.into_iter().collect(),
+        }
+    }
+
+    fn is_alive(&self, cell: &T::Cell) -> bool {
+        self.alive.contains(cell)
+    }
+
+    fn alive(&self) -> Vec<T::Cell> {
+        self.alive.iter().cloned().collect()
+    }
+
+    fn fingerprint(&self) -> u64 {
+        // Summed, as cells come out of the set in no particular order
+        self.alive
+            .iter()
+            .map(|cell| {
+                let mut hasher = DefaultHasher::new();
+                cell.hash(&mut hasher);
+                hasher.finish()
+            })
+            .fold(0, u64::wrapping_add)
+    }
+
+    fn step(&mut self, topology: &T, rule: &Rule) -> usize {
+        let mut counts = HashMap::<T::Cell, usize>::new();
+        for cell in self.alive.iter() {
+            for neighbor in topology.neighbors(cell) {
+                *counts.entry(neighbor).or_default() += 1;
+            }
+        }
+
+        let lonely = if rule.birth.contains(&0) {
+            topology.cells().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let next = counts
+            .keys()
+            .chain(self.alive.iter())
+            .chain(lonely.iter())
+            .filter(|&cell| {
+                let neighbors = counts.get(cell).copied().unwrap_or(0);
+                rule.next(self.alive.contains(cell), neighbors)
+            })
+            .cloned()
+            .collect::<HashSet<_>>();
+
+        let changes = next.symmetric_difference(&self.alive).count();
+        self.alive = next;
+        changes
+    }
+}
+
+/// One byte per cell of an `Adjacency`, 1 when alive, and the buffer the
+/// next generation is written to, so stepping doesn't allocate.
+#[derive(Debug, Clone)]
+pub struct Dense {
+    current: Vec<u8>,
+    next: Vec<u8>,
+}
+
+impl Dense {
+    pub fn cells(&self) -> &[u8] {
+        &self.current
+    }
+}
+
+impl PartialEq for Dense {
+    fn eq(&self, other: &Self) -> bool {
+        self.current == other.current
+    }
+}
+
+impl State<Adjacency> for Dense {
+    fn new(topology: &Adjacency, alive: impl IntoIterator<Item = usize>) -> Self {
+        let mut current = vec![0; topology.len()];
+        for cell in alive {
+            current[cell] = 1;
+        }
+        Self {
+            next: vec![0; current.len()],
+            current,
+        }
+    }
+
+    fn is_alive(&self, &cell: &usize) -> bool {
+        self.current.get(cell) == Some(&1)
+    }
+
+    fn alive(&self) -> Vec<usize> {
+        (0..self.current.len())
+            .filter(|&cell| self.current[cell] == 1)
+            .collect()
+    }
+
+    fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.current.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn step(&mut self, topology: &Adjacency, rule: &Rule) -> usize {
+        let mut changes = 0;
+        for cell in 0..self.current.len() {
+            let neighbors = topology
+                .row(cell)
+                .iter()
+                .map(|&neighbor| self.current[neighbor] as usize)
+                .sum();
+            let next = rule.next(self.current[cell] == 1, neighbors) as u8;
+            changes += (next != self.current[cell]) as usize;
+            self.next[cell] = next;
+        }
+        std::mem::swap(&mut self.current, &mut self.next);
+        changes
+    }
+}
+
+/// How a run ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    /// `generation` is the first one the rule leaves as it is.
+    Stable { generation: usize },
+    /// Generation `start + period` is the same as `start`, and so on.
+    Cycle { start: usize, period: usize },
+}
+
+/// A cellular automaton, its live cells kept the way its topology says.
+#[derive(Debug, Clone)]
+pub struct Life<T: Topology> {
+    topology: T,
+    rule: Rule,
+    state: T::State,
+    generation: usize,
+}
+
+impl<T: Topology> Life<T> {
+    pub fn new(topology: T, rule: Rule, alive: impl IntoIterator<Item = T::Cell>) -> Self {
+        Self {
+            state: T::State::new(&topology, alive),
+            topology,
+            rule,
+            generation: 0,
+        }
+    }
+
+    pub fn topology(&self) -> &T {
+        &self.topology
+    }
+
+    pub fn state(&self) -> &T::State {
+        &self.state
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn population(&self) -> usize {
+        self.state
+            .alive()
+            .iter()
+            .map(|cell| self.topology.weight(cell))
+            .sum()
+    }
+
+    pub fn is_alive(&self, cell: &T::Cell) -> bool {
+        self.state.is_alive(cell)
+    }
+
+    pub fn alive(&self) -> Vec<T::Cell> {
+        self.state.alive()
+    }
+
+    /// Moves to the next generation, returns how many cells changed.
+    pub fn step(&mut self) -> usize {
+        let changes = self.state.step(&self.topology, &self.rule);
+        self.generation += 1;
+        changes
+    }
+
+    pub fn run(&mut self, generations: usize) {
+        for _ in 0..generations {
+            self.step();
+        }
+    }
+
+    /// Steps until a generation is the same as an earlier one, and stops
+    /// there, or gives up after `limit` steps as patterns like gliders
+    /// never repeat. `observe` sees every new generation along with how
+    /// many cells changed to get to it.
+    pub fn run_to_end(
+        &mut self,
+        limit: usize,
+        mut observe: impl FnMut(&Self, usize),
+    ) -> Option<Outcome> {
+        let first = self.generation;
+        let mut seen = HashMap::<u64, Vec<usize>>::new();
+        let mut states = Vec::new();
+        loop {
+            let index = states.len();
+            let earlier = seen.entry(self.state.fingerprint()).or_default();
+            if let Some(&start) = earlier
+                .iter()
+                .find(|&&earlier| states[earlier] == self.state)
+            {
+                return Some(match index - start {
+                    1 => Outcome::Stable {
+                        generation: first + start,
+                    },
+                    period => Outcome::Cycle {
+                        start: first + start,
+                        period,
+                    },
+                });
+            }
+            if index == limit {
+                return None;
+            }
+            earlier.push(index);
+            states.push(self.state.clone());
+            let changes = self.step();
+            observe(self, changes);
+        }
+    }
+}
+
+impl<T: Topology<Cell = [isize; N]>, const N: usize> Life<T> {
+    /// The live cells cut along the first two axes, one grid of rows and
+    /// columns per combination of the other coordinates, ordered by those.
+    /// All grids cover the same rectangle, the one every live cell fits in.
+    pub fn slices(&self) -> Vec<(Vec<isize>, Grid<bool>)> {
+        let cells = self
+            .alive()
+            .iter()
+            .flat_map(|cell| self.topology.images(cell))
+            .collect::<Vec<_>>();
+        let range = |axis: usize| {
+            let values = cells.iter().map(|cell| cell[axis]);
+            (
+                values.clone().min().unwrap_or(0),
+                values.max().unwrap_or(-1),
+            )
+        };
+        let (top, bottom) = range(0);
+        let (left, right) = range(1);
+        let (height, width) = ((bottom - top + 1) as usize, (right - left + 1) as usize);
+
+        let mut slices = BTreeMap::new();
+        for cell in cells.iter() {
+            slices
+                .entry(cell[2..].to_vec())
+                .or_insert_with(|| Grid::from_fn(width, height, |_| false))
+                [((cell[0] - top) as usize, (cell[1] - left) as usize)] = true;
+        }
+        slices.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_space() {
+        let glider = [[1, 0], [2, 1], [0, 2], [1, 2], [2, 2]];
+        let mut life = Life::new(Space::<2>, Rule::conway(), glider.iter().copied());
+        life.run(4);
+        assert_eq!(life.generation(), 4);
+        assert_eq!(life.population(), 5);
+        assert!(glider.iter().all(|&[x, y]| life.is_alive(&[x + 1, y + 1])));
+
+        let mut block = Life::new(
+            Space::<2>,
+            Rule::conway(),
+            vec![[0, 0], [0, 1], [1, 0], [1, 1]],
+        );
+        assert_eq!(
+            block.run_to_end(10, |_, _| {}),
+            Some(Outcome::Stable { generation: 0 })
+        );
+        let mut blinker = Life::new(Space::<2>, Rule::conway(), vec![[0, 0], [0, 1], [0, 2]]);
+        assert_eq!(
+            blinker.run_to_end(10, |_, _| {}),
+            Some(Outcome::Cycle {
+                start: 0,
+                period: 2
+            })
+        );
+        assert_eq!(blinker.generation(), 2);
+
+        let mut changes = Vec::new();
+        let mut glider = Life::new(Space::<2>, Rule::conway(), glider.iter().copied());
+        assert_eq!(
+            glider.run_to_end(10, |_, changed| changes.push(changed)),
+            None
+        );
+        assert_eq!(changes, vec![4; 10]);
+    }
+
+    #[test]
+    fn test_mirrored_space() {
+        let start = [
+            [1, 0, 0, 0],
+            [2, 1, 0, 0],
+            [0, 2, 0, 0],
+            [1, 2, 0, 0],
+            [2, 2, 0, 0],
+        ];
+        let mut full = Life::new(Space::<4>, Rule::conway(), start.iter().copied());
+        let mut mirrored = Life::new(MirroredSpace::<4>, Rule::conway(), start.iter().copied());
+        for _ in 0..4 {
+            full.step();
+            mirrored.step();
+            assert_eq!(mirrored.population(), full.population());
+        }
+        assert!(mirrored
+            .alive()
+            .iter()
+            .all(|cell| cell[2] >= 0 && cell[3] >= 0));
+        assert_eq!(mirrored.slices(), full.slices());
+    }
+
+    #[test]
+    fn test_plane() {
+        // A cell with no live neighbour is born, and dies next to 2 of them
+        let cells = Grid::from_fn(3, 1, |_| true);
+        let mut life = Life::new(
+            Plane::new(cells.clone(), Neighborhood::Adjacent),
+            Rule::new(&[0], &[0, 1]),
+            vec![],
+        );
+        assert_eq!(life.step(), 3);
+        assert_eq!(life.step(), 1);
+        assert_eq!(life.population(), 2);
+
+        let plane = Plane::new(cells, Neighborhood::LineOfSight);
+        assert_eq!(plane.neighbors(&(0, 0)), vec![(0, 1)]);
+        let hidden = Grid::from_fn(3, 1, |(_, column)| column != 1);
+        let plane = Plane::new(hidden, Neighborhood::LineOfSight);
+        assert_eq!(plane.neighbors(&(0, 0)), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn test_adjacency() {
+        let hidden = Grid::from_fn(3, 1, |(_, column)| column != 1);
+        let plane = Plane::new(hidden, Neighborhood::LineOfSight);
+        let cells = plane.cells().unwrap();
+        let adjacency = Adjacency::new(&plane, &cells);
+        assert_eq!(adjacency.len(), 2);
+        assert_eq!(adjacency.row(0), &[1]);
+        assert_eq!(adjacency.row(1), &[0]);
+
+        // The same cells on a plane and on its adjacency step alike
+        let cells = Grid::from_fn(3, 1, |_| true);
+        let plane = Plane::new(cells, Neighborhood::Adjacent);
+        let adjacency = Adjacency::new(&plane, &plane.cells().unwrap());
+        let mut life = Life::new(adjacency, Rule::new(&[0], &[0, 1]), vec![]);
+        assert_eq!(life.step(), 3);
+        assert_eq!(life.step(), 1);
+        assert_eq!(life.population(), 2);
+        assert_eq!(life.state().cells(), &[1, 0, 1]);
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+/// Returns `(g, x, y)` such that `a * x + b * y == g == gcd(a, b)`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
+
+fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    (a as u128 * b as u128 % modulus as u128) as u64
+}
+
+/// `base ^ exponent (mod modulus)` by repeated squaring.
+pub fn pow_mod(base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let mut result = 1 % modulus;
+    let mut base = base % modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// The `x` with `a * x ≡ 1 (mod modulus)`, if `a` and `modulus` are coprime.
+pub fn inverse(a: u64, modulus: u64) -> Option<u64> {
+    let (g, x, _) = extended_gcd(a as i128, modulus as i128);
+    if g == 1 {
+        Some(x.rem_euclid(modulus as i128) as u64)
+    } else {
+        None
+    }
+}
+
+/// Smallest `x` with `base ^ x ≡ target (mod modulus)`, found with
+/// baby-step giant-step in O(sqrt(modulus)) time and memory.
+/// `base` has to be coprime with `modulus`.
+pub fn discrete_log(base: u64, target: u64, modulus: u64) -> Option<u64> {
+    let steps = (modulus as f64).sqrt().ceil() as u64;
+
+    // Baby steps: base ^ j for every j < steps
+    let mut table = HashMap::with_capacity(steps as usize);
+    let mut value = 1 % modulus;
+    for j in 0..steps {
+        table.entry(value).or_insert(j);
+        value = mul_mod(value, base, modulus);
+    }
+
+    // Giant steps: target * base ^ (-i * steps)
+    let factor = inverse(pow_mod(base, steps, modulus), modulus)?;
+    let mut gamma = target % modulus;
+    for i in 0..steps {
+        if let Some(j) = table.get(&gamma) {
+            return Some(i * steps + j);
+        }
+        gamma = mul_mod(gamma, factor, modulus);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discrete_log() {
+        assert_eq!(discrete_log(2, 1, 11), Some(0));
+        assert_eq!(discrete_log(2, 9, 11), Some(6));
+        assert_eq!(discrete_log(3, 13, 17), Some(4));
+        // 2 only generates the quadratic residues mod 7
+        assert_eq!(discrete_log(2, 3, 7), None);
+    }
+}
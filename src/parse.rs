@@ -0,0 +1,114 @@
+use std::{error::Error, fmt, str::FromStr};
+
+/// Where and why a puzzle input couldn't be parsed.
+#[derive(Clone, PartialEq)]
+pub struct ParseError {
+    pub day: usize,
+    /// 1-based line of the input.
+    pub line: usize,
+    /// 1-based column, counted in chars.
+    pub column: usize,
+    pub text: String,
+    pub expected: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {} input, line {}, column {}: expected {}, ",
+            self.day, self.line, self.column, self.expected
+        )?;
+        if self.text.is_empty() {
+            write!(f, "found nothing")
+        } else {
+            write!(f, "found `{}`", self.text)
+        }
+    }
+}
+
+// The runner prints generator errors with `{:#?}`, so that has to read as
+// the diagnostic too
+impl fmt::Debug for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Error for ParseError {}
+
+/// A day's raw input, used to point errors at the exact slice that failed.
+#[derive(Debug, Clone, Copy)]
+pub struct Source<'a> {
+    day: usize,
+    input: &'a str,
+}
+
+impl<'a> Source<'a> {
+    pub fn new(day: usize, input: &'a str) -> Self {
+        Self { day, input }
+    }
+
+    /// `text` has to be a slice of the input for the line and column to be
+    /// right, anything else is reported at the start of the input.
+    pub fn error(&self, text: &str, expected: &'static str) -> ParseError {
+        let offset = (text.as_ptr() as usize)
+            .checked_sub(self.input.as_ptr() as usize)
+            .filter(|&offset| offset <= self.input.len() && self.input.is_char_boundary(offset))
+            .unwrap_or(0);
+        let before = &self.input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+        ParseError {
+            day: self.day,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            text: text.to_string(),
+            expected,
+        }
+    }
+
+    /// An error for input that stops before `expected` shows up.
+    pub fn end(&self, expected: &'static str) -> ParseError {
+        self.error(&self.input[self.input.len()..], expected)
+    }
+
+    /// Fails on the first char of `text` that isn't one of `allowed`.
+    pub fn expect_chars(
+        &self,
+        text: &str,
+        allowed: &str,
+        expected: &'static str,
+    ) -> Result<(), ParseError> {
+        match text.char_indices().find(|&(_, c)| !allowed.contains(c)) {
+            Some((index, c)) => Err(self.error(&text[index..index + c.len_utf8()], expected)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn parse<T: FromStr>(&self, text: &str, expected: &'static str) -> Result<T, ParseError> {
+        text.parse().map_err(|_| self.error(text, expected))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_position() {
+        let input = "12\n34\n5x6";
+        let source = Source::new(1, input);
+        let error = source.error(&input[7..8], "a digit");
+        assert_eq!((error.line, error.column), (3, 2));
+        assert_eq!(
+            error.to_string(),
+            "day 1 input, line 3, column 2: expected a digit, found `x`"
+        );
+
+        assert_eq!(format!("{:#?}", error), error.to_string());
+
+        let error = source.end("a newline");
+        assert_eq!((error.line, error.column), (3, 4));
+    }
+}
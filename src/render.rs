@@ -0,0 +1,161 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use crate::grid::Grid;
+
+/// One generation of a simulation: a title and the grids it is made of,
+/// each under its own label, like the slices of a higher dimensional one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub title: String,
+    pub slices: Vec<(String, Grid<char>)>,
+}
+
+impl Frame {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            slices: Vec::new(),
+        }
+    }
+
+    pub fn slice(mut self, label: impl Into<String>, grid: Grid<char>) -> Self {
+        self.slices.push((label.into(), grid));
+        self
+    }
+}
+
+fn colour(cell: char) -> Option<&'static str> {
+    match cell {
+        '#' => Some("\x1b[1;33m"),
+        'L' => Some("\x1b[32m"),
+        '.' => Some("\x1b[2m"),
+        _ => None,
+    }
+}
+
+/// Writes frames as text, in `directory` either one file per frame or all
+/// of a simulation's frames in a single file, and other pictures next to
+/// them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Renderer {
+    directory: PathBuf,
+    colour: bool,
+    single_file: bool,
+}
+
+impl Renderer {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            colour: false,
+            single_file: false,
+        }
+    }
+
+    /// Colours cells with ANSI escape codes.
+    pub fn colour(&mut self, colour: bool) -> &mut Self {
+        self.colour = colour;
+        self
+    }
+
+    pub fn single_file(&mut self, single_file: bool) -> &mut Self {
+        self.single_file = single_file;
+        self
+    }
+
+    pub fn render(&self, frame: &Frame) -> String {
+        let mut text = format!("{}\n", frame.title);
+        for (label, grid) in frame.slices.iter() {
+            text.push('\n');
+            if !label.is_empty() {
+                text.push_str(label);
+                text.push('\n');
+            }
+            for row in grid.rows() {
+                for &cell in row.iter() {
+                    match colour(cell).filter(|_| self.colour) {
+                        Some(code) => text.push_str(&format!("{}{}\x1b[0m", code, cell)),
+                        None => text.push(cell),
+                    }
+                }
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Writes `frames` to `name.txt`, or to `name/0000.txt` and onwards.
+    pub fn write(&self, name: &str, frames: &[Frame]) -> io::Result<()> {
+        if self.single_file {
+            fs::create_dir_all(&self.directory)?;
+            let mut file = fs::File::create(self.directory.join(format!("{}.txt", name)))?;
+            for (index, frame) in frames.iter().enumerate() {
+                if index > 0 {
+                    writeln!(file)?;
+                }
+                file.write_all(self.render(frame).as_bytes())?;
+            }
+        } else {
+            let directory = self.directory.join(name);
+            fs::create_dir_all(&directory)?;
+            for (index, frame) in frames.iter().enumerate() {
+                fs::write(
+                    directory.join(format!("{:04}.txt", index)),
+                    self.render(frame),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes `contents` to `file_name`, for pictures that aren't frames.
+    pub fn write_file(&self, file_name: &str, contents: &str) -> io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+        fs::write(self.directory.join(file_name), contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_render() {
+        let grid = Grid::from_fn(2, 2, |(row, column)| if row == column { '#' } else { 'L' });
+        let frame = Frame::new("Generation 1").slice("z=0", grid);
+
+        let mut renderer = Renderer::new("frames");
+        assert_eq!(renderer.render(&frame), "Generation 1\n\nz=0\n#L\nL#\n");
+        renderer.colour(true);
+        assert!(renderer
+            .render(&frame)
+            .ends_with("\x1b[32mL\x1b[0m\x1b[1;33m#\x1b[0m\n"));
+    }
+
+    #[test]
+    fn test_write() {
+        let directory = env::temp_dir().join(format!("advent2020-frames-{}", std::process::id()));
+        let frames = vec![
+            Frame::new("0").slice("", Grid::from_fn(1, 1, |_| 'L')),
+            Frame::new("1").slice("", Grid::from_fn(1, 1, |_| '#')),
+        ];
+
+        let mut renderer = Renderer::new(&directory);
+        renderer.write("seats", &frames).unwrap();
+        assert_eq!(
+            fs::read_to_string(directory.join("seats").join("0001.txt")).unwrap(),
+            "1\n\n#\n"
+        );
+        renderer.single_file(true).write("seats", &frames).unwrap();
+        assert_eq!(
+            fs::read_to_string(directory.join("seats.txt")).unwrap(),
+            "0\n\nL\n\n1\n\n#\n"
+        );
+        fs::remove_dir_all(directory).unwrap();
+    }
+}